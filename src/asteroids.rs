@@ -1,57 +1,343 @@
 use bevy::prelude::*;
 
-use crate::{gamedata::GameData, rocket::RocketSpecs};
+use crate::{
+    collision::{mesh_bounding_radius, Collider},
+    difficulty::DifficultyCurve,
+    gamedata::{GameData, RunSeed},
+    gamestate::NewRunEvent,
+    headless::load_or_placeholder,
+    pickups::{PickupKind, PickupSpawnEvent},
+    rocket::{Rocket, RocketKinematics, RocketSpecs},
+    rows::{generate_row, pick_variant, RowParams},
+    throttle::BOOST_SPEED_SCALE,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+    tuning::{AsteroidTuning, Tuning},
+};
 
-const ASTEROID_TEXTURES: &[&str] = &[
-    "assets/models/asteroids/asteroid1/Asteroid1Color.png",
-    "assets/models/asteroids/asteroid2/Asteroid2Color.png",
-    "assets/models/asteroids/asteroid3/Asteroid3Color.png",
-];
-const ASTEROID_MESHES: &[&str] = &[
-    "assets/models/asteroids/asteroid1/Asteroid1.glb",
-    "assets/models/asteroids/asteroid2/Asteroid2.glb",
-    "assets/models/asteroids/asteroid3/Asteroid3.glb",
+// how far past the camera the front of a group has to be before it's recycled, so the biggest
+// asteroid is completely out of view rather than popping out next to the rocket
+const RECYCLE_MARGIN: f32 = 5.0;
+const MAX_TUMBLE_SPEED: f32 = 1.5; // radians per second
+const PICKUP_CHANCE: f32 = 0.08; // of a row having a pickup in its gap
+const MIN_ROW_CLEARANCE: f32 = 2.0; // clear z between the back of one row and the front of the next
+
+// every kind of asteroid the field and the border pick from. a new model only needs an entry here
+struct AsteroidModel {
+    mesh: &'static str,
+    texture: &'static str,
+    min_scale: f32,
+    max_scale: f32,
+    weight: f32, // how often it's picked relative to the others
+}
+
+const ASTEROID_MODELS: &[AsteroidModel] = &[
+    AsteroidModel {
+        mesh: "assets/models/asteroids/asteroid1/Asteroid1.glb",
+        texture: "assets/models/asteroids/asteroid1/Asteroid1Color.png",
+        min_scale: 0.75,
+        max_scale: 1.0,
+        weight: 1.0,
+    },
+    AsteroidModel {
+        mesh: "assets/models/asteroids/asteroid2/Asteroid2.glb",
+        texture: "assets/models/asteroids/asteroid2/Asteroid2Color.png",
+        min_scale: 0.75,
+        max_scale: 1.0,
+        weight: 1.0,
+    },
+    AsteroidModel {
+        mesh: "assets/models/asteroids/asteroid3/Asteroid3.glb",
+        texture: "assets/models/asteroids/asteroid3/Asteroid3Color.png",
+        min_scale: 0.75,
+        max_scale: 1.0,
+        weight: 1.0,
+    },
 ];
 
-struct AsteroidSpawner {
+// an AsteroidModel with its assets loaded
+pub struct AsteroidVariant {
+    pub mesh: Handle<Mesh>,
+    pub material: Handle<StandardMaterial>,
+    pub collider_radius: f32, // the bounding radius of the mesh at a scale of 1.0
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub weight: f32,
+}
+
+impl AsteroidVariant {
+    pub fn random_scale(&self, rng: &fastrand::Rng) -> f32 {
+        lerp(self.min_scale, self.max_scale, rng.f32())
+    }
+
+    // the collider radius at the largest scale
+    pub fn max_radius(&self) -> f32 {
+        self.collider_radius * self.max_scale
+    }
+}
+
+pub struct AsteroidSpawner {
     z_spawn_position: f32, // the z spawn position of asteroids, basically how far ahead of rocket asteroids will spawn
     last_z_position: f32,  // the z position of the last spawned asteroid
     z_interval: f32, // at what distance from last spawned asteroid can a new asteroid be spawned
     z_rand: f32,     // random z offset +/- of z_interval
     min_x_spacing: f32, // the closest two asteroids can be together on the x axis
-    z_velocity: f32, // the speed the asteroids come at you
-    x_velocity: f32, // the asteroids move left or right when you turn
-    x_translation: f32, // the current x position for all asteroids
-    max_x: f32,      // the furthest the asteroids can move left or right relative to the rocket
-    distance_traveled: f32,
-    material_handles: Vec<Handle<StandardMaterial>>,
-    mesh_handles: Vec<Handle<Mesh>>,
+    pub z_velocity: f32, // the speed the asteroids come at you, as of the last simulation step
+    base_z_interval: f32, // z_interval and z_velocity from the tuning file, before difficulty scales them
+    base_z_velocity: f32,
+    // the speed rows are laid out for, from the difficulty alone so a seed gives the same field
+    // however it's flown. as fast as boosting goes, so the way through is always in reach
+    row_z_velocity: f32,
+    max_asteroids_per_row: i32, // not counting the border
+    pub max_x: f32,  // where the border asteroids are, which is as far as the rocket can go left or right
+    pub distance_traveled: f32,
+    last_row_distance: f32, // distance_traveled when the last row was due, which sizes the next one
+    seed: u64,        // the seed of the current run
+    chunk_index: u64, // how many rows have been spawned this run
+    destroyed: u64,   // how many asteroids have been destroyed this run
+    previous_gap_x: f32, // where the way through the last row was, relative to the field
+    variants: Vec<AsteroidVariant>, // one per ASTEROID_MODELS entry
+    pool: Vec<Entity>, // groups that have left the field, waiting to be used for a new row
+    group_mesh: Handle<Mesh>, // the invisible marker every group is drawn with, shared by all of them
+    group_material: Handle<StandardMaterial>,
+}
+
+impl AsteroidSpawner {
+    fn new(tuning: &AsteroidTuning) -> AsteroidSpawner {
+        let mut asteroid_spawner = AsteroidSpawner {
+            z_spawn_position: 0.0,
+            last_z_position: 0.0,
+            z_interval: 0.0,
+            z_rand: 0.0,
+            min_x_spacing: 0.0,
+            z_velocity: 0.0,
+            base_z_interval: 0.0,
+            base_z_velocity: 0.0,
+            row_z_velocity: 0.0,
+            max_asteroids_per_row: 2,
+            max_x: 0.0,
+            distance_traveled: 0.0,
+            last_row_distance: 0.0,
+            seed: 0,
+            chunk_index: 0,
+            destroyed: 0,
+            previous_gap_x: 0.0,
+            variants: vec![],
+            pool: vec![],
+            group_mesh: Handle::default(),
+            group_material: Handle::default(),
+        };
+        asteroid_spawner.apply_tuning(tuning);
+        asteroid_spawner.reset(0);
+        asteroid_spawner
+    }
+
+    // takes effect from the next row on, rows already spawned stay where they are
+    pub fn apply_tuning(&mut self, tuning: &AsteroidTuning) {
+        self.z_spawn_position = tuning.z_spawn_position;
+        self.z_interval = tuning.z_interval;
+        self.z_rand = tuning.z_rand;
+        self.min_x_spacing = tuning.min_x_spacing;
+        self.z_velocity = tuning.z_velocity;
+        self.base_z_interval = tuning.z_interval;
+        self.base_z_velocity = tuning.z_velocity;
+        self.max_x = tuning.max_x;
+    }
+
+    // sets the row spacing and row size for how far into the run the last row was due, which
+    // doesn't depend on how the frames fell. the speed changes every step, see step.
+    // rows are z_rand deep, so closer than that plus the biggest asteroid they'd run into each other
+    pub fn apply_difficulty(&mut self, curve: &DifficultyCurve) {
+        let min_z_interval = self.z_rand + 2.0 * self.max_asteroid_radius() + MIN_ROW_CLEARANCE;
+        self.z_interval = (self.base_z_interval * curve.interval_scale(self.last_row_distance)).max(min_z_interval);
+        self.max_asteroids_per_row = curve.max_asteroids(self.last_row_distance);
+        self.row_z_velocity = self.base_z_velocity * curve.speed_scale(self.last_row_distance) * BOOST_SPEED_SCALE;
+    }
+
+    // moves the spawner towards the rocket for one simulation step at the tuned speed times
+    // speed_scale, which covers the difficulty and anything else that speeds the run up
+    pub fn step(&mut self, speed_scale: f32, delta_seconds: f32) {
+        self.z_velocity = self.base_z_velocity * speed_scale;
+        let distance = self.step_distance(delta_seconds);
+        self.last_z_position += distance;
+        self.distance_traveled += distance;
+    }
+
+    // how far the asteroids come in a step at the last step's speed. outside a run nothing steps
+    // the spawner, and whatever is left keeps flying at that speed
+    pub fn step_distance(&self, delta_seconds: f32) -> f32 {
+        self.z_velocity * delta_seconds
+    }
+
+    // puts everything that changes during a run back to how it was at startup
+    fn reset(&mut self, seed: u64) {
+        // pretend a row was just spawned one interval behind the spawn position so the
+        // first row lands exactly on z_spawn_position
+        self.z_interval = self.base_z_interval;
+        self.z_velocity = self.base_z_velocity;
+        self.row_z_velocity = self.base_z_velocity * BOOST_SPEED_SCALE;
+        self.last_z_position = self.z_spawn_position + self.z_interval;
+        self.distance_traveled = 0.0;
+        self.last_row_distance = 0.0;
+        self.seed = seed;
+        self.chunk_index = 0;
+        self.destroyed = 0;
+        self.previous_gap_x = 0.0; // the rocket starts in the middle
+    }
+
+    // every row gets its own generator derived from the run seed and the row's index so a
+    // seed always produces the same field no matter how the frames fall.
+    // fastrand::Rng isn't Sync, so it can't live in the resource itself
+    fn chunk_rng(&self) -> fastrand::Rng {
+        fastrand::Rng::with_seed(splitmix64(self.seed ^ splitmix64(self.chunk_index)))
+    }
+
+    // like chunk_rng, one generator per destroyed asteroid so replays break them up the same way
+    pub fn fragment_rng(&mut self) -> fastrand::Rng {
+        self.destroyed += 1;
+        fastrand::Rng::with_seed(splitmix64(self.seed ^ splitmix64(!self.destroyed)))
+    }
+
+    // the collider radius of the biggest asteroid any variant can be
+    fn max_asteroid_radius(&self) -> f32 {
+        self.variants.iter().map(AsteroidVariant::max_radius).fold(0.0, f32::max)
+    }
+
+    pub fn pooled_groups(&self) -> usize {
+        self.pool.len()
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// groups are never despawned. once a group has passed the camera it goes back into the pool,
+// hidden and inactive, and is moved back to the spawn position for a later row
+pub struct AsteroidGroup {
+    pub z: f32,       // the simulated z position
+    previous_z: f32, // z one simulation step ago, for interpolation
+    pub active: bool, // false while waiting in the pool
+    slots: Vec<Entity>, // every child, used or not
+    pub fragments: Vec<Entity>, // children left by destroyed asteroids, despawned with the group
+}
+
+impl AsteroidGroup {
+    // an active group at z, laid out with the given children
+    pub fn new(z: f32, slots: Vec<Entity>) -> AsteroidGroup {
+        AsteroidGroup {
+            z,
+            previous_z: z,
+            active: true,
+            slots,
+            fragments: vec![],
+        }
+    }
+
+    // the simulated position of the group, which its transform lags behind by up to one step
+    pub fn position(&self, kinematics: &RocketKinematics) -> Vec3 {
+        Vec3::new(kinematics.field_x(), 0.0, self.z)
+    }
+}
+
+// a field asteroid the rocket has to get past; border asteroids don't have one
+pub struct Asteroid {
+    pub passed: bool, // set once the asteroid is behind the rocket
+}
+
+// on every child of a group. a recycled group keeps its children, so when the new row has
+// fewer asteroids than the group has slots the rest stay hidden until a bigger row comes along
+pub struct AsteroidSlot {
+    pub in_use: bool,
+    field: bool, // field slots also have an Asteroid and a Drift, border slots never do
+}
+
+impl AsteroidSlot {
+    pub fn new(field: bool) -> AsteroidSlot {
+        AsteroidSlot { in_use: true, field }
+    }
+}
+
+// spins an asteroid around its own center. only for looks, colliders are spheres
+pub struct Tumble {
+    axis: Vec3,
+    speed: f32, // radians per second
+}
+
+// sideways motion of a field asteroid relative to its group, bouncing between min_x and max_x.
+// asteroids that don't drift have a velocity of 0
+pub struct Drift {
+    pub x: f32,       // the simulated x relative to the group
+    previous_x: f32, // x one simulation step ago, for interpolation
+    velocity: f32,
+    min_x: f32,
+    max_x: f32,
+}
+
+impl Drift {
+    fn new(x: f32, velocity: f32, min_x: f32, max_x: f32) -> Drift {
+        Drift {
+            x,
+            previous_x: x,
+            velocity,
+            min_x,
+            max_x,
+        }
+    }
+
+    fn fixed(x: f32) -> Drift {
+        Drift::new(x, 0.0, x, x)
+    }
+
+    fn step(&mut self, delta_seconds: f32) {
+        self.previous_x = self.x;
+        self.x += self.velocity * delta_seconds;
+        // turn around at either end of the room it has
+        if self.x < self.min_x {
+            self.x = self.min_x;
+            self.velocity = self.velocity.abs();
+        } else if self.x > self.max_x {
+            self.x = self.max_x;
+            self.velocity = -self.velocity.abs();
+        }
+    }
+}
+
+// the simulated position of a child relative to its group. transforms of drifting asteroids
+// lag behind by up to one step, everything else sits still within its group
+pub fn slot_offset(transform: &Transform, drift: Option<&Drift>) -> Vec3 {
+    let translation = transform.translation();
+    match drift {
+        Some(drift) => Vec3::new(drift.x, translation.y(), translation.z()),
+        None => translation,
+    }
 }
 
-struct AsteroidGroup;
+// where one asteroid of a row goes, worked out before picking the slot that shows it
+struct SlotLayout {
+    variant: usize,
+    transform: Transform,
+    radius: f32,
+    tumble: Tumble,
+    drift: Option<Drift>, // only for field asteroids
+}
 pub struct AsteroidsPlugin;
 
 impl Plugin for AsteroidsPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_resource(AsteroidSpawner {
-            z_spawn_position: -300.0,
-            last_z_position: 0.0,
-            z_interval: 24.0,
-            z_rand: 15.0,
-            min_x_spacing: 2.0,
-            z_velocity: 60.0,
-            x_velocity: 0.0,
-            max_x: 12.0,
-            distance_traveled: 0.0,
-            x_translation: 0.0,
-            material_handles: vec![],
-            mesh_handles: vec![],
-        })
+        // the tuning file replaces these once TuningPlugin has read it
+        app.add_resource(AsteroidSpawner::new(&Tuning::default().asteroids))
         .add_startup_system(load_assets.system())
-        .add_system(spawn_asteroids.system())
-        .add_system(despawn_asteroids.system())
-        .add_system(asteroid_spawner_movement.system())
-        .add_system(asteroid_movement.system());
+        .add_system_to_stage(stage::PRE_UPDATE, reset_asteroids.system())
+        .add_simulation_system(asteroid_movement.system())
+        .add_simulation_system(drift_asteroids.system())
+        .add_simulation_system(recycle_asteroids.system())
+        .add_simulation_system(spawn_asteroids.system())
+        .add_system(interpolate_asteroid_groups.system())
+        .add_system(interpolate_drift.system())
+        .add_system(tumble_asteroids.system());
     }
 }
 
@@ -62,36 +348,93 @@ fn load_assets(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    for path in ASTEROID_TEXTURES {
-        let asteroid_texture_handle = asset_server.load_sync(&mut textures, path).unwrap();
+    for model in ASTEROID_MODELS {
+        let asteroid_texture_handle = load_or_placeholder(&asset_server, &mut textures, model.texture);
 
         let asteroid_material_handle = materials.add(StandardMaterial {
             albedo: Color::rgb(1.0, 1.0, 1.0),
             albedo_texture: Some(asteroid_texture_handle),
             ..Default::default()
         });
-        asteroid_spawner
-            .material_handles
-            .push(asteroid_material_handle);
+
+        let mesh_handle = load_or_placeholder(&asset_server, &mut meshes, model.mesh);
+        let collider_radius = meshes
+            .get(&mesh_handle)
+            .map_or(1.0, mesh_bounding_radius);
+
+        asteroid_spawner.variants.push(AsteroidVariant {
+            mesh: mesh_handle,
+            material: asteroid_material_handle,
+            collider_radius,
+            min_scale: model.min_scale,
+            max_scale: model.max_scale,
+            weight: model.weight,
+        });
+    }
+
+    asteroid_spawner.group_mesh = meshes.add(Mesh::from(shape::Plane { size: 0.0 }));
+    asteroid_spawner.group_material = materials.add(StandardMaterial {
+        albedo: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        shaded: false,
+        ..Default::default()
+    });
+}
+
+fn reset_asteroids(
+    mut commands: Commands,
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    run_seed: Res<RunSeed>,
+    mut asteroid_spawner: ResMut<AsteroidSpawner>,
+    mut asteroid_group_query: Query<(Entity, &mut AsteroidGroup, &mut Draw, &mut Children)>,
+    mut slot_query: Query<(&mut AsteroidSlot, &mut Draw)>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
     }
 
-    for path in ASTEROID_MESHES {
-        let mesh_handle = asset_server.load_sync(&mut meshes, path).unwrap();
-        asteroid_spawner.mesh_handles.push(mesh_handle);
+    for (entity, mut asteroid_group, mut draw, mut children) in &mut asteroid_group_query.iter() {
+        if asteroid_group.active {
+            recycle_group(
+                &mut commands,
+                &mut asteroid_spawner.pool,
+                entity,
+                &mut *asteroid_group,
+                &mut *draw,
+                &mut *children,
+                &mut slot_query,
+            );
+        }
     }
+    asteroid_spawner.reset(run_seed.seed);
 }
 
 fn spawn_asteroids(
     mut commands: Commands,
     game_data: Res<GameData>,
     mut asteroid_spawner: ResMut<AsteroidSpawner>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    kinematics: Res<RocketKinematics>,
+    rocket_specs: Res<RocketSpecs>,
+    mut rocket_query: Query<(&Rocket, &Collider)>,
+    mut asteroid_group_query: Query<(&mut AsteroidGroup, &mut Transform, &mut Draw)>,
+    mut slot_query: Query<(
+        &mut AsteroidSlot,
+        &mut Transform,
+        &mut Draw,
+        &mut Handle<Mesh>,
+        &mut Handle<StandardMaterial>,
+        &mut Collider,
+        &mut Tumble,
+    )>,
+    mut asteroid_query: Query<(&mut Asteroid, &mut Drift)>,
+    mut pickup_events: ResMut<Events<PickupSpawnEvent>>,
 ) {
     match game_data.game_state {
         crate::gamestate::GameState::Menu => return,
         crate::gamestate::GameState::Playing => {}
+        crate::gamestate::GameState::Paused => return,
         crate::gamestate::GameState::Dead => return,
+        crate::gamestate::GameState::Controls => return,
     }
     if asteroid_spawner.last_z_position
         < asteroid_spawner.z_spawn_position + asteroid_spawner.z_interval
@@ -99,197 +442,312 @@ fn spawn_asteroids(
         return;
     }
 
-    let mut seed = (asteroid_spawner.distance_traveled * 100.0).round() as u64;
-    fastrand::seed(seed);
+    // keep rows exactly z_interval apart, however far past the threshold this step went
+    let z_position = asteroid_spawner.last_z_position - asteroid_spawner.z_interval;
+    let overshoot = z_position - asteroid_spawner.z_spawn_position;
+    asteroid_spawner.last_row_distance = asteroid_spawner.distance_traveled - overshoot;
+    let rng = asteroid_spawner.chunk_rng();
+    let mut rocket_radius = 1.0;
+    for (_rocket, collider) in &mut rocket_query.iter() {
+        rocket_radius = collider.radius;
+    }
+    // whichever variants the border ends up with
+    let max_radius = asteroid_spawner.max_asteroid_radius();
+    // the rocket has to stay in the last row's gap until that row's deepest asteroid is past, and
+    // be in this one's before its nearest arrives, so it only has the clear z between them to steer
+    let free_window = (asteroid_spawner.z_interval - asteroid_spawner.z_rand - 2.0 * max_radius).max(0.0);
+    let row = generate_row(
+        &rng,
+        &RowParams {
+            count: rng.i32(0..=asteroid_spawner.max_asteroids_per_row),
+            variants: &asteroid_spawner.variants,
+            max_x: asteroid_spawner.max_x,
+            border_radius: max_radius,
+            z_rand: asteroid_spawner.z_rand,
+            min_x_spacing: asteroid_spawner.min_x_spacing,
+            rocket_radius,
+            previous_gap_x: asteroid_spawner.previous_gap_x,
+            reach: rocket_specs.lateral_reach(free_window / asteroid_spawner.row_z_velocity),
+        },
+    );
 
-    let asteroid_count = fastrand::i32(0..3);
+    let mut field_layouts = vec![];
+    for asteroid in &row.asteroids {
+        let drift = match &asteroid.drift {
+            Some(drift) => Drift::new(asteroid.x, drift.velocity, drift.min_x, drift.max_x),
+            None => Drift::fixed(asteroid.x),
+        };
+        field_layouts.push(SlotLayout {
+            variant: asteroid.variant,
+            transform: Transform::from_translation_rotation_scale(
+                Vec3::new(asteroid.x, 1.0, asteroid.z),
+                random_rotation(&rng),
+                asteroid.scale,
+            ),
+            radius: asteroid.radius,
+            tumble: random_tumble(&rng),
+            drift: Some(drift),
+        });
+    }
 
-    commands
-        .spawn(PbrComponents {
-            mesh: meshes.add(Mesh::from(shape::Plane { size: 0.0 })),
-            material: materials.add(StandardMaterial {
-                albedo: Color::rgba(1.0, 1.0, 1.0, 0.0),
-                shaded: false,
-                ..Default::default()
-            }),
-            transform: Transform::from_translation(Vec3::new(
-                asteroid_spawner.x_translation,
-                0.0,
-                asteroid_spawner.z_spawn_position,
-            )),
-            ..Default::default()
-        })
-        .with(AsteroidGroup)
-        .with_children(|parent| {
-            let mut x_positions: Vec<f32> = vec![];
-            for _ in 0..asteroid_count {
-                seed += 1;
-                fastrand::seed(seed);
-                let asteroid = fastrand::usize(0..2);
-                let z = fastrand::f32() * asteroid_spawner.z_rand;
-                let mut x =
-                    fastrand::f32() * asteroid_spawner.max_x * 2.0 - asteroid_spawner.max_x;
-                for x1 in &x_positions {
-                    while (x1 - x).abs() < asteroid_spawner.min_x_spacing {
-                        seed += 1;
-                        fastrand::seed(seed);
-                        x = fastrand::f32() * asteroid_spawner.max_x * 2.0
-                            - asteroid_spawner.max_x;
-                    }
-                }
-                x_positions.push(x);
-
-                parent.spawn(PbrComponents {
-                    mesh: asteroid_spawner.mesh_handles[asteroid],
-                    material: asteroid_spawner.material_handles[asteroid],
-                    transform: Transform::from_translation_rotation_scale(
-                        Vec3::new(x, 1.0, z),
-                        Quat::from_axis_angle(
-                            Vec3::new(fastrand::f32(), fastrand::f32(), 0.0).normalize(),
-                            fastrand::f32() * 3.14,
-                        ),
-                        fastrand::f32() * 0.25 + 0.75,
-                    ),
-                    ..Default::default()
-                });
-            }
+    // border asteroids
+    let mut border_layouts = vec![];
+    let border_spacing = 4.0;
+    let border_asteroids_count = (asteroid_spawner.z_interval / border_spacing) as usize;
+    for z in 0..border_asteroids_count {
+        for x in &[-asteroid_spawner.max_x, asteroid_spawner.max_x] {
+            let variant = pick_variant(&rng, &asteroid_spawner.variants);
+            let scale = asteroid_spawner.variants[variant].random_scale(&rng);
+            border_layouts.push(SlotLayout {
+                variant,
+                transform: Transform::from_translation_rotation_scale(
+                    Vec3::new(*x, 1.0, z as f32 * border_spacing),
+                    random_rotation(&rng),
+                    scale,
+                ),
+                radius: asteroid_spawner.variants[variant].collider_radius * scale,
+                tumble: random_tumble(&rng),
+                drift: None,
+            });
+        }
+    }
 
-            // border asteroids
-            let border_spacing = 4.0;
-            let border_asteroids_count = (asteroid_spawner.z_interval / border_spacing) as usize;
-            for z in 0..border_asteroids_count {
-                parent.spawn(PbrComponents {
-                    mesh: asteroid_spawner.mesh_handles[border_asteroids_count % 3],
-                    material: asteroid_spawner.material_handles[border_asteroids_count % 3],
-                    transform: Transform::from_translation_rotation_scale(
-                        Vec3::new(-asteroid_spawner.max_x, 1.0, z as f32 * border_spacing),
-                        Quat::from_axis_angle(
-                            Vec3::new(fastrand::f32(), fastrand::f32(), 0.0).normalize(),
-                            fastrand::f32() * 3.14,
-                        ),
-                        fastrand::f32() * 0.25 + 0.75,
-                    ),
-                    ..Default::default()
-                }).spawn(PbrComponents {
-                    mesh: asteroid_spawner.mesh_handles[border_asteroids_count % 3],
-                    material: asteroid_spawner.material_handles[border_asteroids_count % 3],
-                    transform: Transform::from_translation_rotation_scale(
-                        Vec3::new(asteroid_spawner.max_x, 1.0, z as f32 * border_spacing),
-                        Quat::from_axis_angle(
-                            Vec3::new(fastrand::f32(), fastrand::f32(), 0.0).normalize(),
-                            fastrand::f32() * 3.14,
-                        ),
-                        fastrand::f32() * 0.25 + 0.75,
-                    ),
-                    ..Default::default()
-                });
-            }
+    // now and then a pickup in the middle of the gap, decided last so the rest of the row
+    // doesn't depend on it
+    if rng.f32() < PICKUP_CHANCE {
+        pickup_events.send(PickupSpawnEvent {
+            kind: PickupKind::random(&rng),
+            x: row.gap_x,
+            z: z_position + asteroid_spawner.z_rand * 0.5,
         });
+    }
 
-    asteroid_spawner.last_z_position = asteroid_spawner.z_spawn_position; // last_z_position needs to be reduced in asteroid_spawner_movement
-}
+    let group_translation = Vec3::new(kinematics.field_x(), 0.0, z_position);
+    match asteroid_spawner.pool.pop() {
+        Some(group) => {
+            // fill the slots the group already has, and only spawn the ones it's missing
+            let slots = match asteroid_group_query.get_mut::<AsteroidGroup>(group) {
+                Ok(mut asteroid_group) => {
+                    asteroid_group.z = z_position;
+                    asteroid_group.previous_z = z_position;
+                    asteroid_group.active = true;
+                    asteroid_group.slots.clone()
+                }
+                Err(_) => vec![],
+            };
+            if let Ok(mut transform) = asteroid_group_query.get_mut::<Transform>(group) {
+                transform.set_translation(group_translation);
+            }
+            if let Ok(mut draw) = asteroid_group_query.get_mut::<Draw>(group) {
+                draw.is_visible = true;
+            }
 
-// moves the spawner left and right based on keyboard input
-fn asteroid_spawner_movement(
-    time: Res<Time>,
-    game_data: Res<GameData>,
-    mut asteroid_spawner: ResMut<AsteroidSpawner>,
-    keyboard_input: Res<Input<KeyCode>>,
-    rocket_specs: Res<RocketSpecs>,
-) {
-    match game_data.game_state {
-        crate::gamestate::GameState::Menu => return,
-        crate::gamestate::GameState::Playing => {}
-        crate::gamestate::GameState::Dead => return,
-    }
+            let mut field_layouts = field_layouts.into_iter();
+            let mut border_layouts = border_layouts.into_iter();
+            for slot in slots {
+                let field = match slot_query.get::<AsteroidSlot>(slot) {
+                    Ok(asteroid_slot) => asteroid_slot.field,
+                    Err(_) => continue,
+                };
+                let layout = if field { field_layouts.next() } else { border_layouts.next() };
+                if let Ok(mut asteroid_slot) = slot_query.get_mut::<AsteroidSlot>(slot) {
+                    asteroid_slot.in_use = layout.is_some();
+                }
+                if let Ok(mut draw) = slot_query.get_mut::<Draw>(slot) {
+                    draw.is_visible = layout.is_some();
+                }
+                let layout = match layout {
+                    Some(layout) => layout,
+                    None => continue,
+                };
+                let variant = &asteroid_spawner.variants[layout.variant];
+                if let Ok(mut mesh) = slot_query.get_mut::<Handle<Mesh>>(slot) {
+                    *mesh = variant.mesh;
+                }
+                if let Ok(mut material) = slot_query.get_mut::<Handle<StandardMaterial>>(slot) {
+                    *material = variant.material;
+                }
+                if let Ok(mut collider) = slot_query.get_mut::<Collider>(slot) {
+                    collider.radius = layout.radius;
+                }
+                if let Ok(mut transform) = slot_query.get_mut::<Transform>(slot) {
+                    *transform = layout.transform;
+                }
+                if let Ok(mut tumble) = slot_query.get_mut::<Tumble>(slot) {
+                    *tumble = layout.tumble;
+                }
+                if let Ok(mut asteroid) = asteroid_query.get_mut::<Asteroid>(slot) {
+                    asteroid.passed = false;
+                }
+                if let (Ok(mut drift), Some(layout_drift)) = (asteroid_query.get_mut::<Drift>(slot), layout.drift) {
+                    *drift = layout_drift;
+                }
+            }
 
-    // calculate angle rocket will turn and accelerate accordingly
-    let angle = time.delta_seconds * rocket_specs.steering_speed;
-    let percent_turned = angle / rocket_specs.max_steering_angle;
-    let mut x_velocity_change = percent_turned * rocket_specs.max_x_velocity;
-    if keyboard_input.pressed(KeyCode::A) {
-        if asteroid_spawner.x_translation > asteroid_spawner.max_x {
-            x_velocity_change = 0.0;
-        }
-    } else if keyboard_input.pressed(KeyCode::D) {
-        if asteroid_spawner.x_translation < -asteroid_spawner.max_x {
-            x_velocity_change = 0.0;
-        } else {
-            x_velocity_change = -x_velocity_change;
-        }
-    } else {
-        // don't translate if x_velocity is close enough to zero;
-        // otherwise slow down velocity
-        if x_velocity_change.abs() > asteroid_spawner.x_velocity.abs() {
-            asteroid_spawner.x_velocity = 0.0;
-            x_velocity_change = 0.0;
+            let new_slots: Vec<Entity> = field_layouts
+                .chain(border_layouts)
+                .map(|layout| spawn_slot(&mut commands, &asteroid_spawner.variants, layout))
+                .collect();
+            if !new_slots.is_empty() {
+                commands.push_children(group, &new_slots);
+                if let Ok(mut asteroid_group) = asteroid_group_query.get_mut::<AsteroidGroup>(group) {
+                    asteroid_group.slots.extend(new_slots);
+                }
+            }
         }
-
-        if asteroid_spawner.x_velocity < 0.0 {
-            x_velocity_change = x_velocity_change.abs();
-        } else if asteroid_spawner.x_velocity > 0.0 {
-            x_velocity_change = -x_velocity_change.abs();
+        None => {
+            let slots: Vec<Entity> = field_layouts
+                .into_iter()
+                .chain(border_layouts)
+                .map(|layout| spawn_slot(&mut commands, &asteroid_spawner.variants, layout))
+                .collect();
+            commands
+                .spawn(PbrComponents {
+                    mesh: asteroid_spawner.group_mesh,
+                    material: asteroid_spawner.group_material,
+                    transform: Transform::from_translation(group_translation),
+                    ..Default::default()
+                })
+                .with(AsteroidGroup::new(z_position, slots.clone()));
+            let group = commands.current_entity().expect("the group was just spawned");
+            commands.push_children(group, &slots);
         }
     }
 
-    asteroid_spawner.x_velocity += x_velocity_change;
+    asteroid_spawner.last_z_position = z_position; // the spawner moves it along again every step
+    asteroid_spawner.chunk_index += 1;
+    asteroid_spawner.previous_gap_x = row.gap_x;
+}
 
-    asteroid_spawner.x_velocity = clamp(
-        asteroid_spawner.x_velocity,
-        -rocket_specs.max_x_velocity,
-        rocket_specs.max_x_velocity,
-    );
+pub fn random_rotation(rng: &fastrand::Rng) -> Quat {
+    Quat::from_axis_angle(Vec3::new(rng.f32(), rng.f32(), 0.0).normalize(), rng.f32() * std::f32::consts::PI)
+}
 
-    asteroid_spawner.x_translation = clamp(
-        asteroid_spawner.x_translation,
-        -asteroid_spawner.max_x,
-        asteroid_spawner.max_x,
-    );
+pub fn random_tumble(rng: &fastrand::Rng) -> Tumble {
+    Tumble {
+        axis: Vec3::new(rng.f32() - 0.5, rng.f32() - 0.5, rng.f32() - 0.5).normalize(),
+        speed: rng.f32() * MAX_TUMBLE_SPEED,
+    }
+}
+
+// a new child for a group, for when none of its existing slots are free
+fn spawn_slot(commands: &mut Commands, variants: &[AsteroidVariant], layout: SlotLayout) -> Entity {
+    commands
+        .spawn(PbrComponents {
+            mesh: variants[layout.variant].mesh,
+            material: variants[layout.variant].material,
+            transform: layout.transform,
+            ..Default::default()
+        })
+        .with(Collider {
+            radius: layout.radius,
+        })
+        .with(AsteroidSlot::new(layout.drift.is_some()))
+        .with(layout.tumble);
+    if let Some(drift) = layout.drift {
+        commands.with(Asteroid { passed: false }).with(drift);
+    }
+    commands.current_entity().expect("the slot was just spawned")
+}
 
-    asteroid_spawner.x_translation += asteroid_spawner.x_velocity * time.delta_seconds;
-    asteroid_spawner.last_z_position += asteroid_spawner.z_velocity * time.delta_seconds;
-    asteroid_spawner.distance_traveled += asteroid_spawner.z_velocity * time.delta_seconds;
+// hides a group and all of its slots and hands it back to the pool. fragments are despawned,
+// and taken out of the group's children too since despawning doesn't do that
+fn recycle_group(
+    commands: &mut Commands,
+    pool: &mut Vec<Entity>,
+    entity: Entity,
+    asteroid_group: &mut AsteroidGroup,
+    draw: &mut Draw,
+    children: &mut Children,
+    slot_query: &mut Query<(&mut AsteroidSlot, &mut Draw)>,
+) {
+    asteroid_group.active = false;
+    draw.is_visible = false;
+    let fragments = &asteroid_group.fragments;
+    children.0.retain(|child| !fragments.contains(child));
+    for fragment in asteroid_group.fragments.drain(..) {
+        commands.despawn(fragment);
+    }
+    for slot in &asteroid_group.slots {
+        if let Ok(mut asteroid_slot) = slot_query.get_mut::<AsteroidSlot>(*slot) {
+            asteroid_slot.in_use = false;
+        }
+        if let Ok(mut draw) = slot_query.get_mut::<Draw>(*slot) {
+            draw.is_visible = false;
+        }
+    }
+    pool.push(entity);
 }
 
 fn asteroid_movement(
-    time: Res<Time>,
+    timestep: Res<FixedTimestep>,
     asteroid_spawner: Res<AsteroidSpawner>,
-    _asteroid_group: Mut<AsteroidGroup>,
+    mut asteroid_group: Mut<AsteroidGroup>,
+) {
+    if !asteroid_group.active {
+        return;
+    }
+    asteroid_group.previous_z = asteroid_group.z;
+    asteroid_group.z += asteroid_spawner.step_distance(timestep.step);
+}
+
+fn drift_asteroids(timestep: Res<FixedTimestep>, mut drift: Mut<Drift>) {
+    drift.step(timestep.step);
+}
+
+fn interpolate_asteroid_groups(
+    timestep: Res<FixedTimestep>,
+    kinematics: Res<RocketKinematics>,
+    asteroid_group: &AsteroidGroup,
     mut transform: Mut<Transform>,
 ) {
-    transform.translate(Vec3::new(
-        0.0,
+    if !asteroid_group.active {
+        return;
+    }
+    transform.set_translation(Vec3::new(
+        kinematics.interpolated_field_x(timestep.alpha),
         0.0,
-        asteroid_spawner.z_velocity * time.delta_seconds,
+        lerp(asteroid_group.previous_z, asteroid_group.z, timestep.alpha),
     ));
-    let trans = transform.translation().clone();
-    transform.set_translation(Vec3::new(asteroid_spawner.x_translation, 0.0, trans.z()));
 }
 
-fn despawn_asteroids(
-    mut commands: Commands,
-    mut entity_query: Query<Entity>,
-    asteroid_group_query: Query<(&AsteroidGroup, &Transform)>,
-) {
-    for entity in &mut entity_query.iter() {
-        if let Ok(_asteroid_group) = asteroid_group_query.get::<AsteroidGroup>(entity) {
-            // the current entity has an asteroid group component
-            if let Ok(transform) = asteroid_group_query.get::<Transform>(entity) {
-                // the current entity has a transform component
-                if transform.translation().z() > 0.0 {
-                    commands.despawn_recursive(entity);
-                }
-            }
-        }
+fn interpolate_drift(timestep: Res<FixedTimestep>, drift: &Drift, mut transform: Mut<Transform>) {
+    let translation = transform.translation();
+    transform.set_translation(Vec3::new(
+        lerp(drift.previous_x, drift.x, timestep.alpha),
+        translation.y(),
+        translation.z(),
+    ));
+}
+
+fn tumble_asteroids(time: Res<Time>, game_data: Res<GameData>, tumble: &Tumble, mut transform: Mut<Transform>) {
+    if game_data.game_state != crate::gamestate::GameState::Playing {
+        return;
     }
+    // around its own center rather than the group's, so not Transform::rotate
+    let rotation = Quat::from_axis_angle(tumble.axis, tumble.speed * time.delta_seconds) * transform.rotation();
+    transform.set_rotation(rotation);
 }
 
-fn clamp(input: f32, min: f32, max: f32) -> f32 {
-    if input < min {
-        min
-    } else if input > max {
-        max
-    } else {
-        input
+// the field asteroids are at or behind the group's z, so once that's behind the camera the whole
+// group is. only visits groups, however many other entities there are
+fn recycle_asteroids(
+    mut commands: Commands,
+    mut asteroid_spawner: ResMut<AsteroidSpawner>,
+    mut asteroid_group_query: Query<(Entity, &mut AsteroidGroup, &mut Draw, &mut Children)>,
+    mut slot_query: Query<(&mut AsteroidSlot, &mut Draw)>,
+) {
+    for (entity, mut asteroid_group, mut draw, mut children) in &mut asteroid_group_query.iter() {
+        if asteroid_group.active && asteroid_group.z > crate::CAMERA_Z + RECYCLE_MARGIN {
+            recycle_group(
+                &mut commands,
+                &mut asteroid_spawner.pool,
+                entity,
+                &mut *asteroid_group,
+                &mut *draw,
+                &mut *children,
+                &mut slot_query,
+            );
+        }
     }
 }
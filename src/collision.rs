@@ -0,0 +1,217 @@
+use bevy::{prelude::*, render::mesh::VertexAttributeValues};
+
+use crate::{
+    asteroids::{slot_offset, AsteroidGroup, AsteroidSlot, Drift},
+    destruction::Fragment,
+    gamedata::GameData,
+    gamestate::GameState,
+    rocket::{Rocket, RocketKinematics},
+    timestep::AddSimulationSystem,
+};
+
+// bounding sphere around the origin of an entity
+pub struct Collider {
+    pub radius: f32,
+}
+
+impl Collider {
+    pub fn from_mesh(mesh: &Mesh, scale: f32) -> Collider {
+        Collider {
+            radius: mesh_bounding_radius(mesh) * scale,
+        }
+    }
+}
+
+// sent when the rocket hits an asteroid
+pub struct CollisionEvent {
+    pub asteroid: Entity,
+}
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<CollisionEvent>()
+            .add_simulation_system(detect_collisions.system());
+    }
+}
+
+// the distance from the mesh origin to its furthest vertex
+pub fn mesh_bounding_radius(mesh: &Mesh) -> f32 {
+    mesh.attributes
+        .iter()
+        .filter(|attribute| attribute.name == "Vertex_Position")
+        .filter_map(|attribute| match &attribute.values {
+            VertexAttributeValues::Float3(positions) => Some(positions),
+            _ => None,
+        })
+        .flat_map(|positions| positions.iter())
+        .map(|position| Vec3::new(position[0], position[1], position[2]).length())
+        .fold(0.0, f32::max)
+}
+
+pub fn spheres_overlap(a: Vec3, a_radius: f32, b: Vec3, b_radius: f32) -> bool {
+    let radii = a_radius + b_radius;
+    (a - b).length_squared() < radii * radii
+}
+
+// checks after every simulation step rather than once a frame, so a fast asteroid can't pass
+// through the rocket between two frames and a hit doesn't depend on the frame rate
+fn detect_collisions(
+    game_data: Res<GameData>,
+    kinematics: Res<RocketKinematics>,
+    mut collision_events: ResMut<Events<CollisionEvent>>,
+    mut rocket_query: Query<(&Rocket, &Transform, &Collider)>,
+    mut asteroid_group_query: Query<(&AsteroidGroup, &Children)>,
+    collider_query: Query<(&AsteroidSlot, &Collider, &Transform)>,
+    drift_query: Query<&Drift>,
+    fragment_query: Query<(&Fragment, &Collider)>,
+) {
+    match game_data.game_state {
+        GameState::Menu => return,
+        GameState::Playing => {}
+        GameState::Paused => return,
+        GameState::Dead => return,
+        GameState::Controls => return,
+    }
+
+    for (_rocket, rocket_transform, rocket_collider) in &mut rocket_query.iter() {
+        let rocket_position = rocket_transform.translation();
+        for (asteroid_group, children) in &mut asteroid_group_query.iter() {
+            if !asteroid_group.active {
+                continue;
+            }
+            // asteroids are children of the group, so their translation is relative to it.
+            // groups are never rotated or scaled which keeps this a simple offset
+            let group_position = asteroid_group.position(&kinematics);
+            for asteroid in children.iter() {
+                let (offset, radius) = match asteroid_offset(*asteroid, &collider_query, &drift_query, &fragment_query) {
+                    Some(offset_and_radius) => offset_and_radius,
+                    None => continue,
+                };
+                if spheres_overlap(
+                    rocket_position,
+                    rocket_collider.radius,
+                    group_position + offset,
+                    radius,
+                ) {
+                    collision_events.send(CollisionEvent {
+                        asteroid: *asteroid,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// where a child of a group is relative to it and how big it is, for asteroids that are in use
+// and fragments of destroyed ones
+fn asteroid_offset(
+    entity: Entity,
+    collider_query: &Query<(&AsteroidSlot, &Collider, &Transform)>,
+    drift_query: &Query<&Drift>,
+    fragment_query: &Query<(&Fragment, &Collider)>,
+) -> Option<(Vec3, f32)> {
+    if let (Ok(fragment), Ok(collider)) = (
+        fragment_query.get::<Fragment>(entity),
+        fragment_query.get::<Collider>(entity),
+    ) {
+        return Some((fragment.offset, collider.radius));
+    }
+    if !collider_query.get::<AsteroidSlot>(entity).ok()?.in_use {
+        return None;
+    }
+    let collider = collider_query.get::<Collider>(entity).ok()?;
+    let transform = collider_query.get::<Transform>(entity).ok()?;
+    let drift = drift_query.get::<Drift>(entity).ok();
+    Some((slot_offset(&transform, drift.as_deref()), collider.radius))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // runs detect_collisions once on a rocket at the origin and a group at group_z with one border
+    // asteroid at offset within it, the field slid to match lateral_position. returns the hits
+    fn collisions(lateral_position: f32, group_z: f32, offset: Vec3) -> usize {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        resources.insert(GameData {
+            game_state: GameState::Playing,
+            score: 0,
+            bonus: 0,
+            distance_score: 0.0,
+            best_score: 0,
+            run_time: 0.0,
+            replaying: false,
+        });
+        let mut kinematics = RocketKinematics::default();
+        kinematics.lateral_position = lateral_position;
+        resources.insert(kinematics);
+        resources.insert(Events::<CollisionEvent>::default());
+
+        world.spawn((Rocket, Transform::from_translation(Vec3::zero()), Collider { radius: 1.0 }));
+        let asteroid = world.spawn((
+            AsteroidSlot::new(false),
+            Collider { radius: 1.0 },
+            Transform::from_translation(offset),
+        ));
+        world.spawn((
+            AsteroidGroup::new(group_z, vec![asteroid]),
+            Transform::from_translation(Vec3::new(-lateral_position, 0.0, group_z)),
+            Children::with(&[asteroid]),
+        ));
+
+        let mut system = detect_collisions.system();
+        system.initialize(&mut resources);
+        system.update_archetype_access(&world);
+        system.run(&world, &resources);
+        system.run_thread_local(&mut world, &mut resources);
+
+        let collision_events = resources.get::<Events<CollisionEvent>>().expect("collision events were inserted");
+        let mut collision_reader = collision_events.get_reader();
+        collision_reader.iter(&collision_events).count()
+    }
+
+    #[test]
+    fn group_translation_brings_an_asteroid_into_the_rocket() {
+        // 3 to the side of its group, which the field has slid 3 the other way, and 5 behind a
+        // group that's 5 ahead: right on the rocket, though neither offset alone would be
+        assert_eq!(collisions(3.0, -5.0, Vec3::new(3.0, 0.0, 5.0)), 1);
+    }
+
+    #[test]
+    fn group_translation_takes_an_asteroid_past_the_rocket() {
+        // the same asteroid, with the field not slid and the group further ahead, is 3 to the
+        // side of the rocket and 5 ahead of it
+        assert_eq!(collisions(0.0, -10.0, Vec3::new(3.0, 0.0, 5.0)), 0);
+    }
+
+    #[test]
+    fn overlapping_spheres_hit() {
+        assert!(spheres_overlap(Vec3::zero(), 1.0, Vec3::new(1.5, 0.0, 0.0), 1.0));
+        assert!(spheres_overlap(Vec3::new(0.0, 1.0, -3.0), 0.5, Vec3::new(0.0, 1.0, -3.0), 0.5));
+    }
+
+    #[test]
+    fn near_miss_does_not_hit() {
+        assert!(!spheres_overlap(Vec3::zero(), 1.0, Vec3::new(2.01, 0.0, 0.0), 1.0));
+        // diagonally, where the distance along each axis alone would be within reach
+        assert!(!spheres_overlap(Vec3::zero(), 1.0, Vec3::new(1.5, 0.0, 1.5), 1.0));
+    }
+
+    #[test]
+    fn touching_spheres_do_not_hit() {
+        assert!(!spheres_overlap(Vec3::zero(), 1.0, Vec3::new(0.0, 0.0, 2.0), 1.0));
+    }
+
+    #[test]
+    fn bounding_radius_reaches_the_furthest_vertex() {
+        let mesh = Mesh::from(shape::Icosphere {
+            radius: 2.5,
+            subdivisions: 1,
+        });
+        assert!((mesh_bounding_radius(&mesh) - 2.5).abs() < 1e-4);
+        assert!((Collider::from_mesh(&mesh, 2.0).radius - 5.0).abs() < 1e-4);
+    }
+}
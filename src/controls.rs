@@ -0,0 +1,344 @@
+use bevy::{
+    input::gamepad::{Gamepad, GamepadAxis, GamepadAxisType, GamepadButton, GamepadButtonType, GamepadEvent, GamepadEventType},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{data_dir, load_ron, save_ron};
+
+const FILE_NAME: &str = "controls.ron";
+const VERSION: u32 = 1;
+const STICK_DEADZONE: f32 = 0.2; // stick deflection below this counts as centered
+const STICK_MENU_THRESHOLD: f32 = 0.5; // how far the stick has to be pushed to move through menus
+
+// everything the player can do with the keyboard or a gamepad. systems ask Actions about these
+// instead of looking at key codes or buttons, so every key binding can be changed
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    SteerLeft,
+    SteerRight,
+    Boost,
+    Brake,
+    Fire,
+    Pause,
+    MenuUp,
+    MenuDown,
+    Confirm,
+    WatchReplay,
+    OpenControls,
+}
+
+// in the order the controls screen lists them
+pub const ACTIONS: &[Action] = &[
+    Action::SteerLeft,
+    Action::SteerRight,
+    Action::Boost,
+    Action::Brake,
+    Action::Fire,
+    Action::Pause,
+    Action::MenuUp,
+    Action::MenuDown,
+    Action::Confirm,
+    Action::WatchReplay,
+    Action::OpenControls,
+];
+
+impl Action {
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::SteerLeft => "Steer left",
+            Action::SteerRight => "Steer right",
+            Action::Boost => "Boost",
+            Action::Brake => "Brake",
+            Action::Fire => "Fire",
+            Action::Pause => "Pause",
+            Action::MenuUp => "Menu up",
+            Action::MenuDown => "Menu down",
+            Action::Confirm => "Confirm",
+            Action::WatchReplay => "Watch replay",
+            Action::OpenControls => "Controls",
+        }
+    }
+
+    fn default_keys(self) -> Vec<KeyCode> {
+        match self {
+            Action::SteerLeft => vec![KeyCode::A, KeyCode::Left],
+            Action::SteerRight => vec![KeyCode::D, KeyCode::Right],
+            Action::Boost => vec![KeyCode::LShift],
+            Action::Brake => vec![KeyCode::LControl],
+            Action::Fire => vec![KeyCode::Space, KeyCode::F],
+            Action::Pause => vec![KeyCode::Escape, KeyCode::P],
+            Action::MenuUp => vec![KeyCode::Up, KeyCode::W],
+            Action::MenuDown => vec![KeyCode::Down, KeyCode::S],
+            Action::Confirm => vec![KeyCode::Space, KeyCode::Return],
+            Action::WatchReplay => vec![KeyCode::R],
+            Action::OpenControls => vec![KeyCode::C],
+        }
+    }
+
+    // gamepad buttons aren't rebindable, every pad uses these
+    fn gamepad_buttons(self) -> &'static [GamepadButtonType] {
+        match self {
+            Action::SteerLeft => &[GamepadButtonType::DPadLeft],
+            Action::SteerRight => &[GamepadButtonType::DPadRight],
+            Action::Boost => &[GamepadButtonType::RightTrigger, GamepadButtonType::RightTrigger2],
+            Action::Brake => &[GamepadButtonType::LeftTrigger],
+            Action::Fire => &[GamepadButtonType::South, GamepadButtonType::LeftTrigger2],
+            Action::Pause => &[GamepadButtonType::Start],
+            Action::MenuUp => &[GamepadButtonType::DPadUp],
+            Action::MenuDown => &[GamepadButtonType::DPadDown],
+            Action::Confirm => &[GamepadButtonType::South],
+            Action::WatchReplay => &[GamepadButtonType::West],
+            Action::OpenControls => &[GamepadButtonType::Select],
+        }
+    }
+}
+
+// keys that can be bound, named in the bindings file by their KeyCode variant
+pub const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Return, KeyCode::Escape, KeyCode::Back, KeyCode::Tab,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl, KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Apostrophe, KeyCode::Minus,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+];
+
+pub fn key_name(key_code: KeyCode) -> String {
+    format!("{:?}", key_code)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|key_code| key_name(*key_code) == name)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Binding {
+    action: Action,
+    keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ControlsFile {
+    version: u32,
+    bindings: Vec<Binding>,
+}
+
+// the keys bound to each action, read from controls.ron in the data directory
+pub struct Controls {
+    bindings: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl Default for Controls {
+    fn default() -> Controls {
+        Controls {
+            bindings: ACTIONS.iter().map(|action| (*action, action.default_keys())).collect(),
+        }
+    }
+}
+
+impl Controls {
+    // actions missing from the file keep their default keys, so it only has to list what changed.
+    // writes the defaults out on first launch so there is a file to edit
+    pub fn load() -> Controls {
+        let mut controls = Controls::default();
+        if !data_dir().join(FILE_NAME).exists() {
+            controls.save();
+            return controls;
+        }
+
+        match load_ron::<ControlsFile>(FILE_NAME) {
+            Ok(file) if file.version == VERSION => {
+                for binding in file.bindings {
+                    let mut keys = vec![];
+                    for name in &binding.keys {
+                        match key_from_name(name) {
+                            Some(key_code) => keys.push(key_code),
+                            None => println!("ignoring unknown key {} bound to {:?}", name, binding.action),
+                        }
+                    }
+                    controls.set_keys(binding.action, keys);
+                }
+            }
+            Ok(file) => println!(
+                "ignoring controls saved by version {} (expected {})",
+                file.version, VERSION
+            ),
+            Err(e) => println!("using the default controls: {}", e),
+        }
+        controls
+    }
+
+    pub fn save(&self) {
+        let file = ControlsFile {
+            version: VERSION,
+            bindings: self
+                .bindings
+                .iter()
+                .map(|(action, keys)| Binding {
+                    action: *action,
+                    keys: keys.iter().map(|key_code| key_name(*key_code)).collect(),
+                })
+                .collect(),
+        };
+        if let Err(e) = save_ron(FILE_NAME, &file) {
+            println!("failed to save controls: {}", e);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings
+            .iter()
+            .find(|(bound, _keys)| *bound == action)
+            .map_or(&[], |(_action, keys)| keys.as_slice())
+    }
+
+    pub fn set_keys(&mut self, action: Action, keys: Vec<KeyCode>) {
+        if let Some((_action, bound)) = self.bindings.iter_mut().find(|(bound, _keys)| *bound == action) {
+            *bound = keys;
+        }
+    }
+
+    pub fn pressed(&self, keyboard_input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key_code| keyboard_input.pressed(*key_code))
+    }
+
+    pub fn just_pressed(&self, keyboard_input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key_code| keyboard_input.just_pressed(*key_code))
+    }
+
+    // e.g. "A / Left", for showing bindings in the ui
+    pub fn describe(&self, action: Action) -> String {
+        match self.keys(action) {
+            [] => "unbound".into(),
+            keys => keys.iter().map(|key_code| key_name(*key_code)).collect::<Vec<_>>().join(" / "),
+        }
+    }
+}
+
+// the actions held this frame from the keyboard and every connected gamepad, updated before
+// anything else runs. gameplay and screens read this rather than the raw inputs
+#[derive(Default)]
+pub struct Actions {
+    pressed: Vec<Action>,
+    just_pressed: Vec<Action>,
+    gamepad_just_pressed: Vec<Action>, // the subset of just_pressed that came from a gamepad button
+    pub steer: f32, // -1 (full right) to 1 (full left), proportional to how far the stick is pushed
+}
+
+impl Actions {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    // for screens where keys are typed as text, so only a pad press should count
+    pub fn gamepad_just_pressed(&self, action: Action) -> bool {
+        self.gamepad_just_pressed.contains(&action)
+    }
+}
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Actions::default())
+            .add_system_to_stage(stage::PRE_UPDATE, update_actions.system());
+    }
+}
+
+// rescales a stick axis so the edge of the deadzone is 0 and full deflection is still 1
+fn apply_deadzone(value: f32) -> f32 {
+    if value.abs() < STICK_DEADZONE {
+        0.0
+    } else {
+        value.signum() * (value.abs() - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)
+    }
+}
+
+fn update_actions(
+    controls: Res<Controls>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut gamepad_reader: Local<EventReader<GamepadEvent>>,
+    gamepad_events: Res<Events<GamepadEvent>>,
+    mut gamepads: Local<Vec<Gamepad>>,
+    mut previous_stick_y: Local<f32>,
+    mut actions: ResMut<Actions>,
+) {
+    for GamepadEvent(gamepad, event_type) in gamepad_reader.iter(&gamepad_events) {
+        match event_type {
+            GamepadEventType::Connected => gamepads.push(*gamepad),
+            GamepadEventType::Disconnected => gamepads.retain(|connected| connected != gamepad),
+            _ => {}
+        }
+    }
+
+    let mut stick_x: f32 = 0.0;
+    let mut stick_y: f32 = 0.0;
+    for gamepad in gamepads.iter() {
+        let axis = |axis_type| apply_deadzone(gamepad_axes.get(&GamepadAxis(*gamepad, axis_type)).unwrap_or(0.0));
+        // whichever pad is pushed furthest wins
+        let x = axis(GamepadAxisType::LeftStickX);
+        if x.abs() > stick_x.abs() {
+            stick_x = x;
+        }
+        let y = axis(GamepadAxisType::LeftStickY);
+        if y.abs() > stick_y.abs() {
+            stick_y = y;
+        }
+    }
+
+    actions.pressed.clear();
+    actions.just_pressed.clear();
+    actions.gamepad_just_pressed.clear();
+    for action in ACTIONS {
+        let buttons = || {
+            gamepads.iter().flat_map(move |gamepad| {
+                action
+                    .gamepad_buttons()
+                    .iter()
+                    .map(move |button_type| GamepadButton(*gamepad, *button_type))
+            })
+        };
+        if controls.pressed(&keyboard_input, *action) || buttons().any(|button| gamepad_buttons.pressed(button)) {
+            actions.pressed.push(*action);
+        }
+        if buttons().any(|button| gamepad_buttons.just_pressed(button)) {
+            actions.just_pressed.push(*action);
+            actions.gamepad_just_pressed.push(*action);
+        } else if controls.just_pressed(&keyboard_input, *action) {
+            actions.just_pressed.push(*action);
+        }
+    }
+
+    // pushing the stick up or down past the threshold moves through menus once, like a d-pad press
+    if stick_y >= STICK_MENU_THRESHOLD && *previous_stick_y < STICK_MENU_THRESHOLD {
+        actions.just_pressed.push(Action::MenuUp);
+    }
+    if stick_y <= -STICK_MENU_THRESHOLD && *previous_stick_y > -STICK_MENU_THRESHOLD {
+        actions.just_pressed.push(Action::MenuDown);
+    }
+    *previous_stick_y = stick_y;
+
+    // digital steering wins over the stick, left over right
+    actions.steer = if actions.pressed(Action::SteerLeft) {
+        1.0
+    } else if actions.pressed(Action::SteerRight) {
+        -1.0
+    } else {
+        -stick_x
+    };
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::{random_rotation, random_tumble, slot_offset, AsteroidGroup, AsteroidSlot, AsteroidSpawner, Drift},
+    collision::Collider,
+    gamedata::GameData,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+};
+
+const DESTROY_BONUS: i32 = 25;
+const MIN_FRAGMENTS: usize = 2;
+const MAX_FRAGMENTS: usize = 4;
+const FRAGMENT_SCALE: f32 = 0.4; // of the asteroid it broke off
+const MIN_FRAGMENT_SPEED: f32 = 2.0;
+const MAX_FRAGMENT_SPEED: f32 = 5.0;
+const FRAGMENT_LIFETIME: f32 = 2.5; // seconds until a fragment is gone, so the lane clears again
+
+// sent to break up a field asteroid, e.g. when it's shot. border asteroids can't be destroyed
+pub struct DestroyAsteroidEvent {
+    pub asteroid: Entity,
+}
+
+// a piece of a destroyed asteroid, flying outward from where it was. it's a child of the same
+// group so it moves with the field like everything else, and it's just as deadly to touch
+pub struct Fragment {
+    pub offset: Vec3,     // the simulated position relative to the group
+    previous_offset: Vec3, // offset one simulation step ago, for interpolation
+    velocity: Vec3,
+    age: f32,
+}
+
+// moves the fragments along with the field, so it goes before the collision checks
+pub struct FragmentsPlugin;
+
+impl Plugin for FragmentsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_simulation_system(move_fragments.system())
+            .add_system(interpolate_fragments.system());
+    }
+}
+
+// breaks up what was hit within the same step, so it goes after the plugins that send the events
+pub struct DestructionPlugin;
+
+impl Plugin for DestructionPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<DestroyAsteroidEvent>()
+            .add_simulation_system(destroy_asteroids.system());
+    }
+}
+
+// hides the asteroid's slot and spawns its fragments in the same group
+fn destroy_asteroids(
+    mut commands: Commands,
+    mut game_data: ResMut<GameData>,
+    mut asteroid_spawner: ResMut<AsteroidSpawner>,
+    mut destroy_reader: Local<EventReader<DestroyAsteroidEvent>>,
+    destroy_events: Res<Events<DestroyAsteroidEvent>>,
+    mut asteroid_group_query: Query<&mut AsteroidGroup>,
+    mut slot_query: Query<(
+        &mut AsteroidSlot,
+        &mut Draw,
+        &Transform,
+        &Collider,
+        &Drift,
+        &Handle<Mesh>,
+        &Handle<StandardMaterial>,
+        &Parent,
+    )>,
+) {
+    for event in destroy_reader.iter(&destroy_events) {
+        // the same asteroid can be hit twice in one step, only the first counts
+        match slot_query.get_mut::<AsteroidSlot>(event.asteroid) {
+            Ok(mut slot) if slot.in_use => slot.in_use = false,
+            _ => continue,
+        }
+        if let Ok(mut draw) = slot_query.get_mut::<Draw>(event.asteroid) {
+            draw.is_visible = false;
+        }
+        let (center, scale, radius) = match (
+            slot_query.get::<Transform>(event.asteroid),
+            slot_query.get::<Collider>(event.asteroid),
+            slot_query.get::<Drift>(event.asteroid),
+        ) {
+            (Ok(transform), Ok(collider), Ok(drift)) => (
+                slot_offset(&transform, Some(&*drift)),
+                transform.scale().x(),
+                collider.radius,
+            ),
+            _ => continue,
+        };
+        let (mesh, material, group) = match (
+            slot_query.get::<Handle<Mesh>>(event.asteroid),
+            slot_query.get::<Handle<StandardMaterial>>(event.asteroid),
+            slot_query.get::<Parent>(event.asteroid),
+        ) {
+            (Ok(mesh), Ok(material), Ok(parent)) => (*mesh, *material, parent.0),
+            _ => continue,
+        };
+        game_data.bonus += DESTROY_BONUS;
+
+        let rng = asteroid_spawner.fragment_rng();
+        let count = rng.usize(MIN_FRAGMENTS..=MAX_FRAGMENTS);
+        let mut fragments = vec![];
+        for index in 0..count {
+            // spread evenly around the asteroid, give or take
+            let angle = (index as f32 + rng.f32() * 0.5) / count as f32 * std::f32::consts::PI * 2.0;
+            let direction = Vec3::new(angle.cos(), 0.0, angle.sin());
+            let offset = center + direction * radius * 0.5;
+            commands
+                .spawn(PbrComponents {
+                    mesh,
+                    material,
+                    transform: Transform::from_translation_rotation_scale(
+                        offset,
+                        random_rotation(&rng),
+                        scale * FRAGMENT_SCALE,
+                    ),
+                    ..Default::default()
+                })
+                .with(Collider {
+                    radius: radius * FRAGMENT_SCALE,
+                })
+                .with(random_tumble(&rng))
+                .with(Fragment {
+                    offset,
+                    previous_offset: offset,
+                    velocity: direction * lerp(MIN_FRAGMENT_SPEED, MAX_FRAGMENT_SPEED, rng.f32()),
+                    age: 0.0,
+                });
+            fragments.push(commands.current_entity().expect("the fragment was just spawned"));
+        }
+        commands.push_children(group, &fragments);
+        if let Ok(mut asteroid_group) = asteroid_group_query.get_mut::<AsteroidGroup>(group) {
+            asteroid_group.fragments.extend(fragments);
+        }
+    }
+}
+
+fn move_fragments(
+    mut commands: Commands,
+    timestep: Res<FixedTimestep>,
+    mut asteroid_group_query: Query<(&mut AsteroidGroup, &mut Children)>,
+    mut fragment_query: Query<(Entity, &mut Fragment, &Parent)>,
+) {
+    for (entity, mut fragment, parent) in &mut fragment_query.iter() {
+        fragment.previous_offset = fragment.offset;
+        fragment.offset += fragment.velocity * timestep.step;
+        fragment.age += timestep.step;
+        if fragment.age >= FRAGMENT_LIFETIME {
+            if let Ok(mut asteroid_group) = asteroid_group_query.get_mut::<AsteroidGroup>(parent.0) {
+                asteroid_group.fragments.retain(|fragment| *fragment != entity);
+            }
+            // despawning doesn't touch the parent, and groups live forever
+            if let Ok(mut children) = asteroid_group_query.get_mut::<Children>(parent.0) {
+                children.0.retain(|child| *child != entity);
+            }
+            commands.despawn(entity);
+        }
+    }
+}
+
+fn interpolate_fragments(timestep: Res<FixedTimestep>, fragment: &Fragment, mut transform: Mut<Transform>) {
+    transform.set_translation(fragment.previous_offset.lerp(fragment.offset, timestep.alpha));
+}
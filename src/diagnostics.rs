@@ -0,0 +1,60 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics},
+    prelude::*,
+};
+
+use crate::asteroids::{AsteroidGroup, AsteroidSlot, AsteroidSpawner};
+
+// counts that should level off a little while into a run and stay there. one that keeps
+// climbing means something is being created for every row instead of reused
+pub struct GameDiagnosticsPlugin;
+
+impl GameDiagnosticsPlugin {
+    pub const ACTIVE_GROUPS: DiagnosticId = DiagnosticId::from_u128(94113217635204578131026415376920713527);
+    pub const POOLED_GROUPS: DiagnosticId = DiagnosticId::from_u128(217308562093474385262412980761318645103);
+    pub const LIVE_ASTEROIDS: DiagnosticId = DiagnosticId::from_u128(151839214557904036917218395730482196841);
+    pub const MESHES: DiagnosticId = DiagnosticId::from_u128(40721938471026419638570215839471625903);
+    pub const MATERIALS: DiagnosticId = DiagnosticId::from_u128(285610374928163742019374651829307461125);
+}
+
+impl Plugin for GameDiagnosticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup.system())
+            .add_system(measure.system());
+    }
+}
+
+fn setup(mut diagnostics: ResMut<Diagnostics>) {
+    diagnostics.add(Diagnostic::new(GameDiagnosticsPlugin::ACTIVE_GROUPS, "asteroid_groups_active", 20));
+    diagnostics.add(Diagnostic::new(GameDiagnosticsPlugin::POOLED_GROUPS, "asteroid_groups_pooled", 20));
+    diagnostics.add(Diagnostic::new(GameDiagnosticsPlugin::LIVE_ASTEROIDS, "asteroids_live", 20));
+    diagnostics.add(Diagnostic::new(GameDiagnosticsPlugin::MESHES, "meshes", 20));
+    diagnostics.add(Diagnostic::new(GameDiagnosticsPlugin::MATERIALS, "standard_materials", 20));
+}
+
+fn measure(
+    mut diagnostics: ResMut<Diagnostics>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    mut asteroid_group_query: Query<&AsteroidGroup>,
+    mut slot_query: Query<&AsteroidSlot>,
+) {
+    let mut active_groups = 0;
+    for asteroid_group in &mut asteroid_group_query.iter() {
+        if asteroid_group.active {
+            active_groups += 1;
+        }
+    }
+    let mut live_asteroids = 0;
+    for slot in &mut slot_query.iter() {
+        if slot.in_use {
+            live_asteroids += 1;
+        }
+    }
+    diagnostics.add_measurement(GameDiagnosticsPlugin::ACTIVE_GROUPS, active_groups as f64);
+    diagnostics.add_measurement(GameDiagnosticsPlugin::POOLED_GROUPS, asteroid_spawner.pooled_groups() as f64);
+    diagnostics.add_measurement(GameDiagnosticsPlugin::LIVE_ASTEROIDS, live_asteroids as f64);
+    diagnostics.add_measurement(GameDiagnosticsPlugin::MESHES, meshes.iter().count() as f64);
+    diagnostics.add_measurement(GameDiagnosticsPlugin::MATERIALS, materials.iter().count() as f64);
+}
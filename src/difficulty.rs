@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asteroids::AsteroidSpawner,
+    gamedata::GameData,
+    gamestate::GameState,
+    pickups::PowerUps,
+    throttle::Throttle,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+pub const DIFFICULTIES: &[Difficulty] = &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+// how a run gets harder, from the first row to ramp_distance meters in and beyond.
+// speeds and spacings scale the values from the tuning file
+pub struct DifficultyCurve {
+    pub ramp_distance: f32,
+    pub start_speed_scale: f32,
+    pub end_speed_scale: f32,
+    pub start_interval_scale: f32, // row spacing
+    pub end_interval_scale: f32,
+    pub start_max_asteroids: i32, // per row, not counting the border
+    pub end_max_asteroids: i32,
+}
+
+impl Difficulty {
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    // normal starts out the way every run played before there were presets
+    pub fn curve(self) -> DifficultyCurve {
+        match self {
+            Difficulty::Easy => DifficultyCurve {
+                ramp_distance: 6000.0,
+                start_speed_scale: 0.8,
+                end_speed_scale: 1.2,
+                start_interval_scale: 1.2,
+                end_interval_scale: 1.0,
+                start_max_asteroids: 1,
+                end_max_asteroids: 2,
+            },
+            Difficulty::Normal => DifficultyCurve {
+                ramp_distance: 5000.0,
+                start_speed_scale: 1.0,
+                end_speed_scale: 1.6,
+                start_interval_scale: 1.0,
+                end_interval_scale: 0.75,
+                start_max_asteroids: 2,
+                end_max_asteroids: 3,
+            },
+            Difficulty::Hard => DifficultyCurve {
+                ramp_distance: 4000.0,
+                start_speed_scale: 1.2,
+                end_speed_scale: 2.0,
+                start_interval_scale: 0.9,
+                end_interval_scale: 0.6,
+                start_max_asteroids: 2,
+                end_max_asteroids: 4,
+            },
+        }
+    }
+
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Difficulty> {
+        DIFFICULTIES.iter().copied().find(|difficulty| difficulty.to_byte() == byte)
+    }
+}
+
+impl DifficultyCurve {
+    // 0 at the start of a run, 1 once ramp_distance is reached
+    fn progress(&self, distance_traveled: f32) -> f32 {
+        (distance_traveled / self.ramp_distance).max(0.0).min(1.0)
+    }
+
+    pub fn speed_scale(&self, distance_traveled: f32) -> f32 {
+        lerp(self.start_speed_scale, self.end_speed_scale, self.progress(distance_traveled))
+    }
+
+    pub fn interval_scale(&self, distance_traveled: f32) -> f32 {
+        lerp(self.start_interval_scale, self.end_interval_scale, self.progress(distance_traveled))
+    }
+
+    pub fn max_asteroids(&self, distance_traveled: f32) -> i32 {
+        lerp(
+            self.start_max_asteroids as f32,
+            self.end_max_asteroids as f32,
+            self.progress(distance_traveled),
+        )
+        .round() as i32
+    }
+}
+
+pub struct DifficultySettings {
+    pub selected: Difficulty,      // picked on the menu
+    pub current: Difficulty,       // what the current run is played on
+    pub next: Option<Difficulty>, // overrides selected for the next run only, e.g. to watch a replay
+}
+
+impl DifficultySettings {
+    // called whenever a run starts
+    pub fn next_run(&mut self) {
+        self.current = self.next.take().unwrap_or(self.selected);
+    }
+}
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(DifficultySettings {
+            selected: Difficulty::Normal,
+            current: Difficulty::Normal,
+            next: None,
+        })
+        .add_simulation_system(apply_difficulty.system());
+    }
+}
+
+// has to run before the asteroid systems in the simulation stage. moves the spawner a step, with
+// the speed worked out again for every step, so it comes out the same at any frame rate
+fn apply_difficulty(
+    game_data: Res<GameData>,
+    timestep: Res<FixedTimestep>,
+    difficulty: Res<DifficultySettings>,
+    power_ups: Res<PowerUps>,
+    throttle: Res<Throttle>,
+    mut asteroid_spawner: ResMut<AsteroidSpawner>,
+) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    let curve = difficulty.current.curve();
+    asteroid_spawner.apply_difficulty(&curve);
+    let speed_scale =
+        curve.speed_scale(asteroid_spawner.distance_traveled) * power_ups.speed_scale() * throttle.speed_scale;
+    asteroid_spawner.step(speed_scale, timestep.step);
+}
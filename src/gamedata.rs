@@ -4,4 +4,26 @@ use gamestate::GameState;
 pub struct GameData {
     pub game_state: GameState,
     pub score: i32,
+    pub bonus: i32, // points earned on top of distance, e.g. close passes
+    pub distance_score: f32, // points for distance, which are worth more the faster it was covered
+    pub best_score: i32,
+    pub run_time: f32, // seconds spent playing the current run
+    pub replaying: bool, // the current run is a replay rather than live input
+}
+
+// the seed the asteroid field of the current run is generated from
+pub struct RunSeed {
+    pub seed: u64,
+    pub fixed: Option<u64>, // entered on the menu; every run uses it instead of a random seed
+    pub next: Option<u64>,  // used once by the next run, e.g. for a replay
+}
+
+impl RunSeed {
+    pub fn next_run(&mut self) {
+        self.seed = self
+            .next
+            .take()
+            .or(self.fixed)
+            .unwrap_or_else(|| fastrand::u64(..));
+    }
 }
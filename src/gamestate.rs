@@ -1,38 +1,141 @@
-use crate::{gamedata, screens::Menu};
+use crate::{
+    controls::{Action, Actions},
+    difficulty::DifficultySettings,
+    gamedata,
+    screens::Menu,
+    timestep::{AddSimulationSystem, FixedTimestep},
+};
 use bevy::prelude::*;
-use gamedata::GameData;
+use gamedata::{GameData, RunSeed};
 
 #[derive(std::cmp::PartialEq)]
 pub enum GameState {
     Menu,
     Playing,
+    Paused,
     Dead,
+    Controls, // rebinding keys, opened from the menu
+}
+
+// sent whenever a run starts; every plugin with per-run state resets it when it reads this.
+// readers run in stage::PRE_UPDATE so the reset happens before the first frame of the run
+pub struct NewRunEvent;
+
+// sent by screens to move the state machine
+pub enum GameStateRequest {
+    NewRun,
+    MainMenu,
+    Resume,
+    Controls,
 }
 
 pub struct GameStatePlugin;
 
 impl Plugin for GameStatePlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(handle_gamestate_system.system());
+        app.add_event::<NewRunEvent>()
+            .add_event::<GameStateRequest>()
+            .add_system(handle_gamestate_system.system())
+            .add_system(handle_gamestate_requests.system())
+            .add_simulation_system(track_run_time.system());
+    }
+}
+
+fn start_new_run(
+    game_data: &mut GameData,
+    run_seed: &mut RunSeed,
+    difficulty: &mut DifficultySettings,
+    new_run_events: &mut Events<NewRunEvent>,
+) {
+    game_data.game_state = GameState::Playing;
+    run_seed.next_run();
+    difficulty.next_run();
+    game_data.score = 0;
+    game_data.bonus = 0;
+    game_data.distance_score = 0.0;
+    game_data.run_time = 0.0;
+    new_run_events.send(NewRunEvent);
+}
+
+// called in the simulation step the rocket crashes in
+pub fn end_run(game_data: &mut GameData) {
+    game_data.game_state = GameState::Dead;
+    if game_data.score > game_data.best_score {
+        game_data.best_score = game_data.score;
+    }
+}
+
+fn set_menu_visible(menu_screen_query: &mut Query<(&Menu, &mut Draw)>, is_visible: bool) {
+    for (_ss, mut draw) in &mut menu_screen_query.iter() {
+        draw.is_visible = is_visible;
     }
 }
 
-fn handle_gamestate_system(mut game_data: ResMut<GameData>, keyboard_input: Res<Input<KeyCode>>, mut menu_screen_query: Query<(&Menu, &mut Draw)>) {
+fn handle_gamestate_system(
+    mut game_data: ResMut<GameData>,
+    actions: Res<Actions>,
+    mut run_seed: ResMut<RunSeed>,
+    mut difficulty: ResMut<DifficultySettings>,
+    mut new_run_events: ResMut<Events<NewRunEvent>>,
+    mut menu_screen_query: Query<(&Menu, &mut Draw)>,
+) {
     match game_data.game_state {
         GameState::Menu => {
-            if keyboard_input.just_pressed(KeyCode::Space) {
-                game_data.game_state = GameState::Playing;
-                for (_ss, mut draw) in &mut menu_screen_query.iter() {
-                    draw.is_visible = false;
-                }
+            if actions.just_pressed(Action::Confirm) {
+                start_new_run(&mut game_data, &mut run_seed, &mut difficulty, &mut new_run_events);
+                set_menu_visible(&mut menu_screen_query, false);
             }
         }
-        GameState::Playing => {}
-        GameState::Dead => {
-            if keyboard_input.just_pressed(KeyCode::Space) {
+        GameState::Playing => {
+            if actions.just_pressed(Action::Pause) {
+                game_data.game_state = GameState::Paused;
+            }
+        }
+        GameState::Paused => {
+            if actions.just_pressed(Action::Pause) {
                 game_data.game_state = GameState::Playing;
+            }
+        }
+        GameState::Dead => {}
+        GameState::Controls => {}
+    }
+}
+
+fn handle_gamestate_requests(
+    mut game_data: ResMut<GameData>,
+    mut request_reader: Local<EventReader<GameStateRequest>>,
+    requests: Res<Events<GameStateRequest>>,
+    mut run_seed: ResMut<RunSeed>,
+    mut difficulty: ResMut<DifficultySettings>,
+    mut new_run_events: ResMut<Events<NewRunEvent>>,
+    mut menu_screen_query: Query<(&Menu, &mut Draw)>,
+) {
+    for request in request_reader.iter(&requests) {
+        match request {
+            GameStateRequest::NewRun => {
+                start_new_run(&mut game_data, &mut run_seed, &mut difficulty, &mut new_run_events);
+                set_menu_visible(&mut menu_screen_query, false);
                 println!("Playing");
             }
+            GameStateRequest::MainMenu => {
+                game_data.game_state = GameState::Menu;
+                set_menu_visible(&mut menu_screen_query, true);
+            }
+            GameStateRequest::Resume => {
+                if game_data.game_state == GameState::Paused {
+                    game_data.game_state = GameState::Playing;
+                }
+            }
+            GameStateRequest::Controls => {
+                game_data.game_state = GameState::Controls;
+                set_menu_visible(&mut menu_screen_query, false);
+            }
         }
     }
 }
+
+fn track_run_time(timestep: Res<FixedTimestep>, mut game_data: ResMut<GameData>) {
+    if game_data.game_state == GameState::Playing {
+        game_data.run_time += timestep.step;
+    }
+}
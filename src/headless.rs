@@ -0,0 +1,529 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    asset::AssetPlugin,
+    core::CorePlugin,
+    diagnostic::{Diagnostics, DiagnosticsPlugin},
+    ecs::Resource,
+    input::{
+        keyboard::{ElementState, KeyboardInput},
+        InputPlugin,
+    },
+    prelude::*,
+    transform::TransformPlugin,
+};
+
+use crate::{
+    asteroids::{AsteroidGroup, AsteroidsPlugin},
+    collision::CollisionPlugin,
+    controls::{ActionsPlugin, Controls},
+    destruction::{DestructionPlugin, FragmentsPlugin},
+    diagnostics::GameDiagnosticsPlugin,
+    difficulty::{DifficultyPlugin, DifficultySettings},
+    gamedata::{GameData, RunSeed},
+    gamestate::{GameState, GameStatePlugin},
+    lives::LivesPlugin,
+    pickups::PickupsPlugin,
+    replay::{Replay, ReplayPlugin, Replays},
+    rocket::RocketPlugin,
+    score::ScorePlugin,
+    throttle::ThrottlePlugin,
+    timestep::{FixedTimestep, TimestepPlugin},
+    tuning::TuningPlugin,
+    weapon::WeaponPlugin,
+};
+
+// loads an asset, falling back to a placeholder handle when it can't be loaded.
+// headless runs have no gltf or image loaders, so every model and texture ends up here
+pub fn load_or_placeholder<T: Resource>(asset_server: &AssetServer, assets: &mut Assets<T>, path: &str) -> Handle<T> {
+    match asset_server.load_sync(assets, path) {
+        Ok(handle) => handle,
+        Err(e) => {
+            println!("couldn't load {} ({:?}), using a placeholder", path, e);
+            Handle::default()
+        }
+    }
+}
+
+// stands in for the entities a bigger game would have, see HeadlessApp::spawn_filler
+struct Filler;
+
+// the gameplay plugins without a window, renderer or ui, stepped one frame at a time
+pub struct HeadlessApp {
+    app: App,
+}
+
+impl HeadlessApp {
+    // every update simulates exactly frame_time seconds, regardless of how long it really took
+    pub fn new(frame_time: f32, seed: Option<u64>) -> HeadlessApp {
+        let mut builder = App::build();
+        builder
+            .add_plugin(CorePlugin)
+            .add_plugin(TransformPlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(AssetPlugin)
+            .add_plugin(DiagnosticsPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<Texture>()
+            .add_asset::<StandardMaterial>()
+            .add_plugin(ActionsPlugin)
+            .add_plugin(TimestepPlugin)
+            .add_plugin(TuningPlugin)
+            .add_plugin(GameStatePlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(ThrottlePlugin)
+            .add_plugin(DifficultyPlugin)
+            .add_plugin(RocketPlugin)
+            .add_plugin(AsteroidsPlugin)
+            .add_plugin(FragmentsPlugin)
+            .add_plugin(WeaponPlugin)
+            .add_plugin(CollisionPlugin)
+            .add_plugin(LivesPlugin)
+            .add_plugin(DestructionPlugin)
+            .add_plugin(PickupsPlugin)
+            .add_plugin(ScorePlugin)
+            .add_plugin(GameDiagnosticsPlugin)
+            .add_resource(GameData {
+                game_state: GameState::Menu,
+                score: 0,
+                bonus: 0,
+                distance_score: 0.0,
+                best_score: 0,
+                run_time: 0.0,
+                replaying: false,
+            })
+            .add_resource(RunSeed {
+                seed: 0,
+                fixed: seed,
+                next: None,
+            })
+            // the default bindings rather than the player's, so the keys below mean the same everywhere
+            .add_resource(Controls::default());
+
+        let mut app = builder.app;
+        if let Some(mut timestep) = app.resources.get_mut::<FixedTimestep>() {
+            timestep.frame_time = Some(frame_time);
+        }
+        app.startup_schedule.initialize(&mut app.resources);
+        app.startup_executor.run(
+            &mut app.startup_schedule,
+            &mut app.world,
+            &mut app.resources,
+        );
+        HeadlessApp { app }
+    }
+
+    pub fn press(&mut self, key_code: KeyCode) {
+        self.send_key(key_code, ElementState::Pressed);
+    }
+
+    pub fn release(&mut self, key_code: KeyCode) {
+        self.send_key(key_code, ElementState::Released);
+    }
+
+    fn send_key(&mut self, key_code: KeyCode, state: ElementState) {
+        if let Some(mut events) = self.app.resources.get_mut::<Events<KeyboardInput>>() {
+            events.send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key_code),
+                state,
+            });
+        }
+    }
+
+    // leaves the menu the same way a player would
+    pub fn start_run(&mut self) {
+        self.press(KeyCode::Space);
+        self.advance(1);
+        self.release(KeyCode::Space);
+        self.advance(1);
+    }
+
+    // plays a recorded run back on the next start_run, e.g. as a regression fixture
+    pub fn watch(&mut self, replay: Replay) {
+        let resources = &self.app.resources;
+        let mut run_seed = resources.get_mut::<RunSeed>().expect("the headless app has a run seed");
+        let mut difficulty = resources.get_mut::<DifficultySettings>().expect("the headless app has difficulties");
+        let mut replays = resources.get_mut::<Replays>().expect("the headless app records replays");
+        replays.watch(replay, &mut run_seed, &mut difficulty);
+    }
+
+    // entities with nothing but a marker, which every system that looks at all entities pays for.
+    // no transform either, so transform propagation doesn't drown out what's being measured
+    pub fn spawn_filler(&mut self, count: usize) {
+        for _ in 0..count {
+            self.app.world.spawn((Filler,));
+        }
+    }
+
+    // finds the active asteroid groups `passes` times over, first by looking every entity up the
+    // way a Query<Entity> system would, then through a query of only the groups. returns how long
+    // each took, after checking that both found the same groups
+    pub fn time_group_scans(&self, passes: u32) -> (Duration, Duration) {
+        let world = &self.app.world;
+        let mut all_found = 0;
+        let start = Instant::now();
+        for _ in 0..passes {
+            for entity in &mut world.query::<Entity>().iter() {
+                if let Ok(asteroid_group) = world.get::<AsteroidGroup>(entity) {
+                    if asteroid_group.active {
+                        all_found += 1;
+                    }
+                }
+            }
+        }
+        let all_entities = start.elapsed();
+
+        let mut groups_found = 0;
+        let start = Instant::now();
+        for _ in 0..passes {
+            for asteroid_group in &mut world.query::<&AsteroidGroup>().iter() {
+                if asteroid_group.active {
+                    groups_found += 1;
+                }
+            }
+        }
+        let groups_only = start.elapsed();
+
+        assert_eq!(all_found, groups_found);
+        (all_entities, groups_only)
+    }
+
+    pub fn advance(&mut self, frames: u32) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    pub fn resource<T: Resource, R>(&self, read: impl FnOnce(&T) -> R) -> R {
+        let resource = self
+            .app
+            .resources
+            .get::<T>()
+            .expect("resource isn't part of the headless app");
+        read(&resource)
+    }
+
+    // the translation of every entity that has a C component
+    #[cfg(test)]
+    pub fn translations<C: bevy::ecs::Component>(&self) -> Vec<Vec3> {
+        self.app
+            .world
+            .query::<(&C, &Transform)>()
+            .iter()
+            .map(|(_component, transform)| transform.translation())
+            .collect()
+    }
+}
+
+// `cargo run -- --headless <frames> [seed]`: plays a run at 60 fps weaving left and right,
+// then prints where it ended up
+pub fn run(frames: u32, seed: Option<u64>) {
+    let mut headless = HeadlessApp::new(1.0 / 60.0, seed);
+    headless.start_run();
+
+    let mut frame = 0;
+    while frame < frames && headless.resource(|game_data: &GameData| game_data.game_state == GameState::Playing) {
+        let key = if (frame / 60) % 2 == 0 { KeyCode::A } else { KeyCode::D };
+        headless.press(key);
+        headless.advance(30);
+        headless.release(key);
+        headless.advance(30);
+        frame += 60;
+    }
+
+    print_summary(&headless);
+}
+
+// `cargo run --release -- --bench <entities>`: times a minute of flying with that many extra
+// entities in the world. frame times that grow with the entity count mean some system visits
+// every entity instead of just the ones it's about. then compares finding the asteroid groups by
+// scanning every entity, like recycle_asteroids used to, against querying only the groups
+pub fn run_benchmark(entities: usize) {
+    let mut headless = HeadlessApp::new(1.0 / 60.0, Some(0));
+    headless.spawn_filler(entities);
+    headless.start_run();
+
+    let frames = 3600;
+    let start = Instant::now();
+    headless.advance(frames);
+    let elapsed = start.elapsed();
+    println!(
+        "{} extra entities: {} frames in {:.2?}, {:.3}ms per frame",
+        entities,
+        frames,
+        elapsed,
+        elapsed.as_secs_f64() * 1000.0 / frames as f64
+    );
+
+    let (all_entities, groups_only) = headless.time_group_scans(frames);
+    let per_frame = |duration: Duration| duration.as_secs_f64() * 1000.0 / frames as f64;
+    println!(
+        "finding the groups: {:.4}ms per frame scanning every entity, {:.4}ms querying only groups",
+        per_frame(all_entities),
+        per_frame(groups_only)
+    );
+}
+
+// `cargo run -- --replay <file>`: plays a replay from the data directory back headlessly
+pub fn run_replay(file_name: &str) {
+    let replay = match Replay::load(file_name) {
+        Ok(replay) => replay,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let mut headless = HeadlessApp::new(1.0 / 60.0, None);
+    headless.watch(replay);
+    headless.start_run();
+    while headless.resource(|game_data: &GameData| game_data.game_state == GameState::Playing) {
+        headless.advance(60);
+    }
+    print_summary(&headless);
+}
+
+fn print_summary(headless: &HeadlessApp) {
+    let seed = headless.resource(|run_seed: &RunSeed| run_seed.seed);
+    let (score, run_time, dead) = headless.resource(|game_data: &GameData| {
+        (game_data.score, game_data.run_time, game_data.game_state == GameState::Dead)
+    });
+    let count = |id| {
+        headless.resource(|diagnostics: &Diagnostics| {
+            diagnostics.get(id).and_then(|diagnostic| diagnostic.value()).unwrap_or(0.0)
+        })
+    };
+    println!(
+        "seed {}: score {} after {:.1}s, {} asteroid groups alive, {}",
+        seed,
+        score,
+        run_time,
+        count(GameDiagnosticsPlugin::ACTIVE_GROUPS),
+        if dead { "crashed" } else { "still flying" }
+    );
+    println!(
+        "{} asteroids live, {} groups pooled, {} meshes, {} materials",
+        count(GameDiagnosticsPlugin::LIVE_ASTEROIDS),
+        count(GameDiagnosticsPlugin::POOLED_GROUPS),
+        count(GameDiagnosticsPlugin::MESHES),
+        count(GameDiagnosticsPlugin::MATERIALS)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        asteroids::{AsteroidGroup, AsteroidSpawner},
+        lives::Lives,
+        pickups::{PickupKind, PickupSpawnEvent, PowerUps, PICKUP_KINDS},
+        replay::Replays,
+        rocket::{Rocket, RocketKinematics},
+        throttle::Throttle,
+        timestep::STEP,
+        weapon::Weapon,
+    };
+
+    const SEED: u64 = 42;
+    const SCRIPT_STEPS: u32 = 120 * 8;
+    const SCRIPT_PICKUP_STEP: u32 = 120 * 3; // a second after the rocket last steered
+
+    fn played_steps(headless: &HeadlessApp) -> u32 {
+        headless.resource(|game_data: &GameData| (game_data.run_time / STEP).round() as u32)
+    }
+
+    // what a run ended up as, compared between runs that should play out the same
+    fn outcome(headless: &HeadlessApp) -> (bool, u32, u32, u32, f32, f32) {
+        let (dead, score) = headless.resource(|game_data: &GameData| {
+            (game_data.game_state == GameState::Dead, game_data.score)
+        });
+        let (lives, shield_charges) = headless.resource(|lives: &Lives| (lives.lives, lives.shield_charges));
+        let lateral_position = headless.resource(|kinematics: &RocketKinematics| kinematics.lateral_position);
+        let distance = headless.resource(|asteroid_spawner: &AsteroidSpawner| asteroid_spawner.distance_traveled);
+        (dead, score, lives, shield_charges, lateral_position, distance)
+    }
+
+    // plays the first `run_steps` simulation steps of a run with frames of frame_time seconds,
+    // weaving left and right every two seconds. inputs only change on multiples of 4 steps, so
+    // 30 fps frames see them at exactly the same steps as faster ones
+    fn fly(frame_time: f32, run_steps: u32) -> HeadlessApp {
+        let mut headless = HeadlessApp::new(frame_time, Some(SEED));
+        headless.start_run();
+        let mut held = None;
+        while played_steps(&headless) < run_steps
+            && headless.resource(|game_data: &GameData| game_data.game_state == GameState::Playing)
+        {
+            let key = if (played_steps(&headless) / 240) % 2 == 0 { KeyCode::A } else { KeyCode::D };
+            if held != Some(key) {
+                if let Some(released) = held {
+                    headless.release(released);
+                }
+                headless.press(key);
+                held = Some(key);
+            }
+            headless.advance(1);
+        }
+        headless
+    }
+
+    // the keys the scripted run holds from a step on: firing all along, weaving, then boosting
+    // straight ahead, then braking and weaving again. like in fly, they change on multiples of 4 steps
+    fn scripted_keys(step: u32) -> Vec<KeyCode> {
+        let weave = if (step / 120) % 2 == 0 { KeyCode::A } else { KeyCode::D };
+        match step {
+            0..=239 => vec![KeyCode::F, weave],
+            240..=479 => vec![KeyCode::F, KeyCode::LShift],
+            _ => vec![KeyCode::F, KeyCode::LControl, weave],
+        }
+    }
+
+    // whether the next frame simulates a step. an event sent before a frame that doesn't would be
+    // dropped by the one after, before anything got to read it
+    fn steps_next_frame(headless: &HeadlessApp) -> bool {
+        headless.resource(|timestep: &FixedTimestep| {
+            timestep.alpha + timestep.frame_time.unwrap_or(0.0) / timestep.step >= 1.0
+        })
+    }
+
+    // a pickup right ahead of the rocket, which it flies into within a few steps
+    fn place_pickup(headless: &HeadlessApp, kind: PickupKind) {
+        let x = headless.resource(|kinematics: &RocketKinematics| kinematics.lateral_position);
+        let mut pickup_events = headless
+            .app
+            .resources
+            .get_mut::<Events<PickupSpawnEvent>>()
+            .expect("the headless app spawns pickups");
+        pickup_events.send(PickupSpawnEvent { kind, x, z: -10.0 });
+    }
+
+    // plays the scripted run with frames of frame_time seconds, picking up a slow motion on the way
+    fn fly_scripted(frame_time: f32) -> HeadlessApp {
+        let mut headless = HeadlessApp::new(frame_time, Some(SEED));
+        headless.start_run();
+        let mut held: Vec<KeyCode> = vec![];
+        let mut pickup_placed = false;
+        while played_steps(&headless) < SCRIPT_STEPS
+            && headless.resource(|game_data: &GameData| game_data.game_state == GameState::Playing)
+        {
+            let step = played_steps(&headless);
+            let keys = scripted_keys(step);
+            for key in held.iter().filter(|key| !keys.contains(key)) {
+                headless.release(*key);
+            }
+            for key in keys.iter().filter(|key| !held.contains(key)) {
+                headless.press(*key);
+            }
+            held = keys;
+            if !pickup_placed && step >= SCRIPT_PICKUP_STEP && steps_next_frame(&headless) {
+                assert_eq!(step, SCRIPT_PICKUP_STEP);
+                place_pickup(&headless, PickupKind::SlowMotion);
+                pickup_placed = true;
+            }
+            headless.advance(1);
+        }
+        headless
+    }
+
+    // what outcome leaves out: the power-ups, the laser and the throttle
+    fn equipment(headless: &HeadlessApp) -> (Vec<f32>, f32, bool, f32, f32) {
+        let power_ups = headless.resource(|power_ups: &PowerUps| {
+            PICKUP_KINDS.iter().map(|kind| power_ups.remaining(*kind)).collect::<Vec<_>>()
+        });
+        let (heat, overheated) = headless.resource(|weapon: &Weapon| (weapon.heat, weapon.overheated));
+        let (energy, speed_scale) = headless.resource(|throttle: &Throttle| (throttle.energy, throttle.speed_scale));
+        (power_ups, heat, overheated, energy, speed_scale)
+    }
+
+    #[test]
+    fn plays_the_same_at_30_and_240_fps() {
+        let run_steps = 120 * 20;
+        let slow = fly(STEP * 4.0, run_steps); // 30 fps
+        let fast = fly(STEP * 0.5, run_steps); // 240 fps
+
+        // every system runs once per step either way, so not even a float may differ
+        assert_eq!(played_steps(&slow), played_steps(&fast));
+        assert!(outcome(&slow) == outcome(&fast));
+    }
+
+    #[test]
+    fn fires_boosts_and_picks_up_the_same_at_30_and_240_fps() {
+        let slow = fly_scripted(STEP * 4.0); // 30 fps
+        let fast = fly_scripted(STEP * 0.5); // 240 fps
+
+        // the script got through, and the pickup was collected and is still running
+        assert_eq!(played_steps(&slow), SCRIPT_STEPS);
+        assert!(slow.resource(|power_ups: &PowerUps| power_ups.remaining(PickupKind::SlowMotion)) > 0.0);
+        assert!(slow.resource(|throttle: &Throttle| throttle.energy) < 1.0);
+
+        assert_eq!(played_steps(&slow), played_steps(&fast));
+        assert!(outcome(&slow) == outcome(&fast));
+        assert!(equipment(&slow) == equipment(&fast));
+    }
+
+    #[test]
+    fn flies_straight_for_two_seconds() {
+        let mut headless = HeadlessApp::new(1.0 / 60.0, Some(SEED));
+        headless.start_run();
+        let start_distance = headless.resource(|asteroid_spawner: &AsteroidSpawner| asteroid_spawner.distance_traveled);
+        let start_time = headless.resource(|game_data: &GameData| game_data.run_time);
+        let nearest_group = |headless: &HeadlessApp| {
+            headless
+                .translations::<AsteroidGroup>()
+                .iter()
+                .map(|translation| translation.z())
+                .fold(std::f32::MIN, f32::max)
+        };
+        let start_nearest = nearest_group(&headless);
+        headless.advance(120);
+
+        // the first row spawns far enough ahead that nothing reaches the rocket this soon
+        let (playing, run_time) = headless.resource(|game_data: &GameData| {
+            (game_data.game_state == GameState::Playing, game_data.run_time)
+        });
+        assert!(playing);
+        assert!((run_time - start_time - 2.0).abs() < 1e-3);
+
+        let (distance, z_velocity) = headless.resource(|asteroid_spawner: &AsteroidSpawner| {
+            (asteroid_spawner.distance_traveled - start_distance, asteroid_spawner.z_velocity)
+        });
+        assert!(z_velocity > 0.0);
+        // the difficulty curve barely speeds things up over two seconds
+        assert!((distance - 2.0 * z_velocity).abs() < 0.05 * distance);
+
+        // the rocket stays put while the field moves past it
+        for translation in headless.translations::<Rocket>() {
+            assert_eq!(translation, Vec3::zero());
+        }
+        // and the rows have come closer, without any passing the camera yet
+        let nearest = nearest_group(&headless);
+        assert!(nearest > start_nearest);
+        assert!(nearest < crate::CAMERA_Z);
+    }
+
+    #[test]
+    fn replays_play_out_like_the_recorded_run() {
+        let run_steps = 120 * 20;
+        let recorded = fly(1.0 / 60.0, run_steps);
+        let replay = recorded
+            .resource(|replays: &Replays| replays.recording.clone())
+            .expect("a live run is recorded");
+        // through the file format too, which is what players actually share
+        let replay = Replay::from_bytes(&replay.to_bytes()).expect("the recording reads back");
+
+        let mut watched = HeadlessApp::new(1.0 / 60.0, None);
+        watched.watch(replay);
+        watched.start_run();
+        assert!(watched.resource(|game_data: &GameData| game_data.replaying));
+        let target_steps = played_steps(&recorded);
+        while played_steps(&watched) < target_steps
+            && watched.resource(|game_data: &GameData| game_data.game_state == GameState::Playing)
+        {
+            watched.advance(1);
+        }
+
+        assert_eq!(played_steps(&watched), target_steps);
+        assert_eq!(watched.resource(|run_seed: &RunSeed| run_seed.seed), SEED);
+        // the same steps with the same inputs, so nothing may differ at all
+        assert!(outcome(&watched) == outcome(&recorded));
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::Difficulty,
+    storage::{load_ron, save_ron},
+};
+
+const FILE_NAME: &str = "highscores.ron";
+const VERSION: u32 = 1; // bump when HighScoreEntry changes in a way old files can't be read as
+pub const MAX_ENTRIES: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: i32,
+    pub distance: f32,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub date: u64, // seconds since the unix epoch
+    #[serde(default)]
+    pub replay: Option<String>, // file name of the run's replay in the data directory
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>, // none for runs from before there were difficulties
+}
+
+#[derive(Serialize, Deserialize)]
+struct HighScoreFile {
+    version: u32,
+    entries: Vec<HighScoreEntry>,
+}
+
+// the best runs, highest score first
+#[derive(Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+    pub fn load() -> HighScores {
+        match load_ron::<HighScoreFile>(FILE_NAME) {
+            Ok(file) if file.version == VERSION => HighScores {
+                entries: file.entries,
+            },
+            Ok(file) => {
+                println!(
+                    "ignoring high scores saved by version {} (expected {})",
+                    file.version, VERSION
+                );
+                HighScores::default()
+            }
+            // a missing file just means nobody has played yet
+            Err(e) => {
+                println!("no high scores loaded: {}", e);
+                HighScores::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let file = HighScoreFile {
+            version: VERSION,
+            entries: self.entries.clone(),
+        };
+        if let Err(e) = save_ron(FILE_NAME, &file) {
+            println!("failed to save high scores: {}", e);
+        }
+    }
+
+    // true if a run with this score would make it into the table
+    pub fn qualifies(&self, score: i32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().map_or(true, |last| score > last.score))
+    }
+
+    pub fn insert(&mut self, entry: HighScoreEntry) {
+        let position = self
+            .entries
+            .iter()
+            .position(|existing| entry.score > existing.score)
+            .unwrap_or_else(|| self.entries.len());
+        self.entries.insert(position, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+}
+
+// seconds since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+// SystemTime::now panics on wasm32-unknown-unknown, so entries made there are undated
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> u64 {
+    0
+}
+
+// formats seconds since the unix epoch as yyyy-mm-dd (utc)
+pub fn format_date(timestamp: u64) -> String {
+    // civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
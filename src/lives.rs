@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collision::CollisionEvent,
+    destruction::DestroyAsteroidEvent,
+    gamedata::GameData,
+    gamestate::{end_run, GameState, NewRunEvent},
+    rocket::Rocket,
+    timestep::{AddSimulationSystem, FixedTimestep},
+    tuning::Tuning,
+};
+
+pub const MAX_SHIELD_CHARGES: u32 = 3;
+const INVULNERABLE_TIME: f32 = 1.5; // after a hit, to get clear of whatever caused it
+const BLINKS_PER_SECOND: f32 = 8.0; // how often the rocket flashes while invulnerable
+
+// what every run starts with
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LivesTuning {
+    pub lives: u32,
+    pub shield_charges: u32,
+}
+
+// how many more hits the rocket can take this run. a hit uses up a shield charge if there is
+// one and a life otherwise, and the run only ends with the last life
+#[derive(Default)]
+pub struct Lives {
+    pub lives: u32,
+    pub shield_charges: u32,
+    invulnerable: f32, // seconds left in which hits don't count
+}
+
+impl Lives {
+    fn new(tuning: &LivesTuning) -> Lives {
+        Lives {
+            lives: tuning.lives,
+            shield_charges: tuning.shield_charges.min(MAX_SHIELD_CHARGES),
+            invulnerable: 0.0,
+        }
+    }
+
+    pub fn add_shield_charge(&mut self) {
+        self.shield_charges = (self.shield_charges + 1).min(MAX_SHIELD_CHARGES);
+    }
+
+    pub fn add_life(&mut self) {
+        self.lives += 1;
+    }
+
+    pub fn invulnerable(&self) -> bool {
+        self.invulnerable > 0.0
+    }
+}
+
+pub struct LivesPlugin;
+
+impl Plugin for LivesPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // the tuning file replaces these once TuningPlugin has read it
+        let tuning = Tuning::default().lives;
+        app.add_resource(Lives::new(&tuning))
+            .add_resource(tuning)
+            .add_system_to_stage(stage::PRE_UPDATE, reset_lives.system())
+            .add_simulation_system(tick_invulnerability.system())
+            .add_simulation_system(resolve_collisions.system())
+            .add_system(blink_rocket.system());
+    }
+}
+
+fn reset_lives(
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    tuning: Res<LivesTuning>,
+    mut lives: ResMut<Lives>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    *lives = Lives::new(&tuning);
+}
+
+fn tick_invulnerability(game_data: Res<GameData>, timestep: Res<FixedTimestep>, mut lives: ResMut<Lives>) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    lives.invulnerable = (lives.invulnerable - timestep.step).max(0.0);
+}
+
+// decides what the rocket's collisions do. whatever takes the hit also rams the asteroid out of
+// the way, and the run only crashes once there's nothing left to take it. a crash ends the run
+// right away, so nothing in the steps after it still counts
+fn resolve_collisions(
+    mut game_data: ResMut<GameData>,
+    mut lives: ResMut<Lives>,
+    mut collision_reader: Local<EventReader<CollisionEvent>>,
+    collision_events: Res<Events<CollisionEvent>>,
+    mut destroy_events: ResMut<Events<DestroyAsteroidEvent>>,
+) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    for event in collision_reader.iter(&collision_events) {
+        if lives.invulnerable() {
+            continue;
+        }
+        if lives.shield_charges > 0 {
+            lives.shield_charges -= 1;
+        } else {
+            lives.lives = lives.lives.saturating_sub(1);
+            if lives.lives == 0 {
+                end_run(&mut game_data);
+                break;
+            }
+        }
+        lives.invulnerable = INVULNERABLE_TIME;
+        destroy_events.send(DestroyAsteroidEvent {
+            asteroid: event.asteroid,
+        });
+    }
+}
+
+fn blink_rocket(lives: Res<Lives>, _rocket: &Rocket, mut draw: Mut<Draw>) {
+    draw.is_visible = !lives.invulnerable() || (lives.invulnerable * BLINKS_PER_SECOND) as i32 % 2 == 0;
+}
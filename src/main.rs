@@ -1,17 +1,80 @@
+// bevy systems take one parameter per resource and query they use, and spell out the
+// components of each query, so both lints fire on ordinary systems
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 use asteroids::AsteroidsPlugin;
 use bevy::{prelude::*};
-use gamedata::GameData;
+use collision::CollisionPlugin;
+use controls::{ActionsPlugin, Controls};
+use destruction::{DestructionPlugin, FragmentsPlugin};
+use diagnostics::GameDiagnosticsPlugin;
+use difficulty::DifficultyPlugin;
+use gamedata::{GameData, RunSeed};
 use gamestate::{GameState, GameStatePlugin};
+use highscores::HighScores;
+use lives::LivesPlugin;
+use replay::ReplayPlugin;
 use rocket::RocketPlugin;
+use pickups::PickupsPlugin;
+use score::ScorePlugin;
 use screens::ScreensPlugin;
+use throttle::ThrottlePlugin;
+use timestep::TimestepPlugin;
+use tuning::TuningPlugin;
+use weapon::WeaponPlugin;
 mod asteroids;
+mod collision;
+mod controls;
+mod destruction;
+mod diagnostics;
+mod difficulty;
 mod gamedata;
 mod gamestate;
+mod headless;
+mod highscores;
+mod lives;
+mod pickups;
+mod replay;
 mod rocket;
+mod rows;
+mod score;
 mod screens;
+mod storage;
+mod throttle;
+mod timestep;
+mod tuning;
+mod weapon;
+
+// how far behind the rocket the camera is; asteroids stay until they've passed it
+pub const CAMERA_Z: f32 = 20.0;
 
 // the app entry point. hopefully you recognize it from the examples above!
 fn main() {
+    // `--headless <frames> [seed]` simulates a run without opening a window,
+    // `--replay <file>` plays a saved replay back the same way,
+    // `--bench <entities>` times the simulation with that many extra entities around
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--headless") {
+        let frames = args.get(2).and_then(|frames| frames.parse().ok()).unwrap_or(3600);
+        let seed = args.get(3).and_then(|seed| seed.parse().ok());
+        headless::run(frames, seed);
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("--replay") {
+        headless::run_replay(args.get(2).map_or("", String::as_str));
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("--bench") {
+        let entities = args.get(2).and_then(|entities| entities.parse().ok()).unwrap_or(10000);
+        headless::run_benchmark(entities);
+        return;
+    }
+
+    let high_scores = HighScores::load();
+    let controls = Controls::load();
+    let best_score = high_scores.entries.first().map_or(0, |entry| entry.score);
+
     App::build()
         .add_resource(WindowDescriptor {
             title: "Ha! a Title".into(),
@@ -20,15 +83,41 @@ fn main() {
         .add_resource(ClearColor(Color::rgb(0.0, 0.0, 0.005)))
         .add_resource(Msaa { samples: 8 })
         .add_default_plugins()
+        .add_plugin(ActionsPlugin)
+        .add_plugin(TimestepPlugin)
+        .add_plugin(TuningPlugin)
         .add_plugin(GameStatePlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(ThrottlePlugin)
+        .add_plugin(DifficultyPlugin)
         .add_plugin(RocketPlugin)
         .add_plugin(AsteroidsPlugin)
+        .add_plugin(FragmentsPlugin)
+        .add_plugin(WeaponPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(LivesPlugin)
+        .add_plugin(DestructionPlugin)
+        .add_plugin(PickupsPlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(GameDiagnosticsPlugin)
         .add_plugin(ScreensPlugin)
         .add_startup_system(setup.system())
         .add_resource(GameData {
             game_state: GameState::Menu,
             score: 0,
+            bonus: 0,
+            distance_score: 0.0,
+            best_score,
+            run_time: 0.0,
+            replaying: false,
+        })
+        .add_resource(RunSeed {
+            seed: 0,
+            fixed: None,
+            next: None,
         })
+        .add_resource(high_scores)
+        .add_resource(controls)
         .run();
 }
 
@@ -36,7 +125,7 @@ fn setup(mut commands: Commands) {
     commands
         .spawn(Camera3dComponents {
             transform: Transform::new(Mat4::face_toward(
-                Vec3::new(0.0, 9.0, 20.0),
+                Vec3::new(0.0, 9.0, CAMERA_Z),
                 Vec3::new(0.0, 0.0, 0.0),
                 Vec3::new(0.0, 1.0, 0.0),
             )),
@@ -0,0 +1,299 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::AsteroidSpawner,
+    collision::Collider,
+    gamedata::GameData,
+    gamestate::{GameState, NewRunEvent},
+    lives::Lives,
+    rocket::{Rocket, RocketKinematics},
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+};
+
+const PICKUP_RADIUS: f32 = 0.6;
+const PICKUP_HEIGHT: f32 = 1.0; // level with the asteroids
+const RECYCLE_Z: f32 = crate::CAMERA_Z + 5.0; // despawned once this far behind the rocket
+const EFFECT_DURATION: f32 = 8.0; // seconds every timed power-up lasts
+const SLOW_MOTION_SCALE: f32 = 0.5; // of the asteroids' speed
+const SCORE_MULTIPLIER: i32 = 2;
+const MAGNET_RANGE: f32 = 8.0; // how far to either side the magnet reaches
+const MAGNET_SPEED: f32 = 12.0; // how fast it pulls pickups into the rocket's lane
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PickupKind {
+    Shield,
+    SlowMotion,
+    Magnet,
+    ScoreMultiplier,
+    ExtraLife,
+}
+
+pub const PICKUP_KINDS: &[PickupKind] = &[
+    PickupKind::Shield,
+    PickupKind::SlowMotion,
+    PickupKind::Magnet,
+    PickupKind::ScoreMultiplier,
+    PickupKind::ExtraLife,
+];
+
+impl PickupKind {
+    pub fn random(rng: &fastrand::Rng) -> PickupKind {
+        PICKUP_KINDS[rng.usize(..PICKUP_KINDS.len())]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PickupKind::Shield => "Shield charge",
+            PickupKind::SlowMotion => "Slow motion",
+            PickupKind::Magnet => "Magnet",
+            PickupKind::ScoreMultiplier => "Score x2",
+            PickupKind::ExtraLife => "Extra life",
+        }
+    }
+
+    // whether it lasts for a while, rather than adding a shield charge or a life
+    pub fn timed(self) -> bool {
+        match self {
+            PickupKind::Shield | PickupKind::ExtraLife => false,
+            PickupKind::SlowMotion | PickupKind::Magnet | PickupKind::ScoreMultiplier => true,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PickupKind::Shield => Color::rgb(0.2, 0.6, 1.0),
+            PickupKind::SlowMotion => Color::rgb(0.6, 0.3, 1.0),
+            PickupKind::Magnet => Color::rgb(1.0, 0.3, 0.3),
+            PickupKind::ScoreMultiplier => Color::rgb(1.0, 0.85, 0.2),
+            PickupKind::ExtraLife => Color::rgb(0.3, 1.0, 0.4),
+        }
+    }
+}
+
+// sent by spawn_asteroids for a pickup in the gap of the row it just laid out
+pub struct PickupSpawnEvent {
+    pub kind: PickupKind,
+    pub x: f32, // relative to the asteroid field
+    pub z: f32,
+}
+
+// a pickup floating in the field. like a laser, x is relative to the asteroid field
+pub struct Pickup {
+    kind: PickupKind,
+    x: f32,
+    previous_x: f32,
+    z: f32,
+    previous_z: f32,
+}
+
+// the timed power-ups the rocket has picked up this run, as seconds left.
+// shield charges and extra lives go to Lives instead
+#[derive(Default)]
+pub struct PowerUps {
+    slow_motion: f32,
+    magnet: f32,
+    score_multiplier: f32,
+}
+
+impl PowerUps {
+    // seconds left, always 0 for kinds that aren't timed
+    pub fn remaining(&self, kind: PickupKind) -> f32 {
+        match kind {
+            PickupKind::SlowMotion => self.slow_motion,
+            PickupKind::Magnet => self.magnet,
+            PickupKind::ScoreMultiplier => self.score_multiplier,
+            PickupKind::Shield | PickupKind::ExtraLife => 0.0,
+        }
+    }
+
+    fn collect(&mut self, kind: PickupKind, lives: &mut Lives) {
+        match kind {
+            PickupKind::Shield => lives.add_shield_charge(),
+            PickupKind::SlowMotion => self.slow_motion = EFFECT_DURATION,
+            PickupKind::Magnet => self.magnet = EFFECT_DURATION,
+            PickupKind::ScoreMultiplier => self.score_multiplier = EFFECT_DURATION,
+            PickupKind::ExtraLife => lives.add_life(),
+        }
+    }
+
+    fn tick(&mut self, seconds: f32) {
+        for timer in &mut [&mut self.slow_motion, &mut self.magnet, &mut self.score_multiplier] {
+            **timer = (**timer - seconds).max(0.0);
+        }
+    }
+
+    // what the asteroids' speed is scaled by
+    pub fn speed_scale(&self) -> f32 {
+        if self.slow_motion > 0.0 {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    // what points are multiplied by
+    pub fn score_multiplier(&self) -> i32 {
+        if self.score_multiplier > 0.0 {
+            SCORE_MULTIPLIER
+        } else {
+            1
+        }
+    }
+}
+
+struct PickupAssets {
+    mesh: Handle<Mesh>,
+    materials: Vec<Handle<StandardMaterial>>, // one per PICKUP_KINDS entry
+}
+
+pub struct PickupsPlugin;
+
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(PowerUps::default())
+            .add_event::<PickupSpawnEvent>()
+            .add_startup_system(setup.system())
+            .add_system_to_stage(stage::PRE_UPDATE, reset_pickups.system())
+            .add_simulation_system(tick_power_ups.system())
+            .add_simulation_system(move_pickups.system())
+            .add_simulation_system(spawn_pickups.system())
+            .add_system(interpolate_pickups.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(PickupAssets {
+        mesh: meshes.add(Mesh::from(shape::Icosphere {
+            radius: PICKUP_RADIUS,
+            subdivisions: 2,
+        })),
+        materials: PICKUP_KINDS
+            .iter()
+            .map(|kind| {
+                materials.add(StandardMaterial {
+                    albedo: kind.color(),
+                    shaded: false,
+                    ..Default::default()
+                })
+            })
+            .collect(),
+    });
+}
+
+fn reset_pickups(
+    mut commands: Commands,
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    mut power_ups: ResMut<PowerUps>,
+    mut pickup_query: Query<(Entity, &Pickup)>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    *power_ups = PowerUps::default();
+    for (entity, _pickup) in &mut pickup_query.iter() {
+        commands.despawn(entity);
+    }
+}
+
+fn tick_power_ups(game_data: Res<GameData>, timestep: Res<FixedTimestep>, mut power_ups: ResMut<PowerUps>) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    power_ups.tick(timestep.step);
+}
+
+// after move_pickups, so a new pickup starts moving in the next step like the row it's in
+fn spawn_pickups(
+    mut commands: Commands,
+    pickup_assets: Res<PickupAssets>,
+    kinematics: Res<RocketKinematics>,
+    mut spawn_reader: Local<EventReader<PickupSpawnEvent>>,
+    spawn_events: Res<Events<PickupSpawnEvent>>,
+) {
+    for event in spawn_reader.iter(&spawn_events) {
+        let index = PICKUP_KINDS.iter().position(|kind| *kind == event.kind).unwrap_or(0);
+        commands
+            .spawn(PbrComponents {
+                mesh: pickup_assets.mesh,
+                material: pickup_assets.materials[index],
+                transform: Transform::from_translation(Vec3::new(
+                    kinematics.field_x() + event.x,
+                    PICKUP_HEIGHT,
+                    event.z,
+                )),
+                ..Default::default()
+            })
+            .with(Pickup {
+                kind: event.kind,
+                x: event.x,
+                previous_x: event.x,
+                z: event.z,
+                previous_z: event.z,
+            });
+    }
+}
+
+// pickups travel with the asteroids, and with a magnet also sideways towards the rocket. they're
+// collected at the first step the rocket touches them, so a fast one can't slip through it.
+// whatever one gives takes effect from the next step
+fn move_pickups(
+    mut commands: Commands,
+    game_data: Res<GameData>,
+    timestep: Res<FixedTimestep>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    kinematics: Res<RocketKinematics>,
+    mut power_ups: ResMut<PowerUps>,
+    mut lives: ResMut<Lives>,
+    mut rocket_query: Query<(&Rocket, &Transform, &Collider)>,
+    mut pickup_query: Query<(Entity, &mut Pickup)>,
+) {
+    let mut rocket = None; // its z and radius
+    for (_rocket, transform, collider) in &mut rocket_query.iter() {
+        rocket = Some((transform.translation().z(), collider.radius));
+    }
+
+    for (entity, mut pickup) in &mut pickup_query.iter() {
+        pickup.previous_x = pickup.x;
+        pickup.previous_z = pickup.z;
+        pickup.z += asteroid_spawner.step_distance(timestep.step);
+        let rocket_x = kinematics.lateral_position; // relative to the field
+        let offset = rocket_x - pickup.x;
+        if power_ups.magnet > 0.0 && offset.abs() < MAGNET_RANGE {
+            let pull = MAGNET_SPEED * timestep.step;
+            pickup.x += offset.max(-pull).min(pull);
+        }
+
+        let collected = match rocket {
+            Some((rocket_z, rocket_radius)) if game_data.game_state == GameState::Playing => {
+                // on the field's plane like laser hits, pickups float level with the asteroids
+                let dx = pickup.x - rocket_x;
+                let dz = pickup.z - rocket_z;
+                let reach = rocket_radius + PICKUP_RADIUS;
+                dx * dx + dz * dz < reach * reach
+            }
+            _ => false,
+        };
+        if collected {
+            power_ups.collect(pickup.kind, &mut lives);
+        }
+        if collected || pickup.z > RECYCLE_Z {
+            commands.despawn(entity);
+        }
+    }
+}
+
+fn interpolate_pickups(
+    timestep: Res<FixedTimestep>,
+    kinematics: Res<RocketKinematics>,
+    pickup: &Pickup,
+    mut transform: Mut<Transform>,
+) {
+    transform.set_translation(Vec3::new(
+        kinematics.interpolated_field_x(timestep.alpha) + lerp(pickup.previous_x, pickup.x, timestep.alpha),
+        PICKUP_HEIGHT,
+        lerp(pickup.previous_z, pickup.z, timestep.alpha),
+    ));
+}
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{Action, Actions},
+    difficulty::{Difficulty, DifficultySettings},
+    gamedata::{GameData, RunSeed},
+    gamestate::{GameState, GameStateRequest, NewRunEvent},
+    highscores::now,
+    storage::{load_bytes, save_bytes},
+    timestep::FixedTimestep,
+};
+
+const MAGIC: &[u8; 4] = b"RKRP";
+// bump when the format or the simulation changes
+const VERSION: u8 = 15;
+const HEADER_LENGTH: usize = 14;
+
+// what the player does during this frame's simulation steps, read from Actions or from a
+// replay. gameplay systems only ever look at this, never at the keys or pads themselves
+#[derive(Default, Clone, Copy, PartialEq)]
+pub struct PlayerInput {
+    pub steer: f32, // -1 (full right) to 1 (full left)
+    pub fire: bool,
+    pub boost: bool,
+    pub brake: bool,
+}
+
+const FIRE_BIT: u8 = 1;
+const BOOST_BIT: u8 = 2;
+const BRAKE_BIT: u8 = 4;
+
+impl PlayerInput {
+    fn steer_byte(self) -> u8 {
+        (self.steer.max(-1.0).min(1.0) * 127.0).round() as i8 as u8
+    }
+
+    // every held button is a bit, so new ones don't change the format
+    fn buttons_byte(self) -> u8 {
+        let bit = |held: bool, bit: u8| if held { bit } else { 0 };
+        bit(self.fire, FIRE_BIT) | bit(self.boost, BOOST_BIT) | bit(self.brake, BRAKE_BIT)
+    }
+
+    fn from_bytes(steer: u8, buttons: u8) -> PlayerInput {
+        PlayerInput {
+            steer: steer as i8 as f32 / 127.0,
+            fire: buttons & FIRE_BIT != 0,
+            boost: buttons & BOOST_BIT != 0,
+            brake: buttons & BRAKE_BIT != 0,
+        }
+    }
+}
+
+// one rendered frame of a run: how many simulation steps it advanced and the input during them.
+// frames are kept, not just steps, since input is only sampled once a frame
+#[derive(Clone, Copy, PartialEq)]
+struct ReplayFrame {
+    steps: u8,
+    steer: u8,
+    buttons: u8,
+}
+
+#[derive(Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, difficulty: Difficulty) -> Replay {
+        Replay {
+            seed,
+            difficulty,
+            frames: vec![],
+        }
+    }
+
+    // magic, version, seed, difficulty, then runs of identical frames as (steps, steer, buttons, count)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(self.difficulty.to_byte());
+        let mut frames = self.frames.iter().peekable();
+        while let Some(frame) = frames.next() {
+            let mut count: u16 = 1;
+            while count < u16::MAX && frames.peek() == Some(&frame) {
+                frames.next();
+                count += 1;
+            }
+            bytes.push(frame.steps);
+            bytes.push(frame.steer);
+            bytes.push(frame.buttons);
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        if bytes.len() < HEADER_LENGTH || &bytes[0..4] != MAGIC {
+            return Err("not a replay file".into());
+        }
+        if bytes[4] != VERSION {
+            return Err(format!("replay version {} isn't supported (expected {})", bytes[4], VERSION));
+        }
+        let mut seed = [0; 8];
+        seed.copy_from_slice(&bytes[5..13]);
+        let difficulty = Difficulty::from_byte(bytes[13]).ok_or("replay has an unknown difficulty")?;
+        let mut replay = Replay::new(u64::from_le_bytes(seed), difficulty);
+
+        let runs = &bytes[HEADER_LENGTH..];
+        if runs.len() % 5 != 0 {
+            return Err("replay file is truncated".into());
+        }
+        for run in runs.chunks(5) {
+            let frame = ReplayFrame {
+                steps: run[0],
+                steer: run[1],
+                buttons: run[2],
+            };
+            let count = u16::from_le_bytes([run[3], run[4]]);
+            replay.frames.extend((0..count).map(|_| frame));
+        }
+        Ok(replay)
+    }
+
+    pub fn load(file_name: &str) -> Result<Replay, String> {
+        Replay::from_bytes(&load_bytes(file_name)?)
+    }
+
+    pub fn save(&self, file_name: &str) -> Result<(), String> {
+        save_bytes(file_name, &self.to_bytes())
+    }
+}
+
+#[derive(Default)]
+pub struct Replays {
+    pub recording: Option<Replay>, // the current run, or the last one once it's over
+    pub pending: Option<Replay>,   // played back instead of recording on the next run
+    playback: Option<Replay>,
+    cursor: usize,
+    step_budget: u32, // steps real time allows the playback to be ahead by, so it plays at normal speed
+}
+
+impl Replays {
+    // queues a replay and the seed and difficulty it was recorded with for the next run
+    pub fn watch(&mut self, replay: Replay, run_seed: &mut RunSeed, difficulty: &mut DifficultySettings) {
+        run_seed.next = Some(replay.seed);
+        difficulty.next = Some(replay.difficulty);
+        self.pending = Some(replay);
+    }
+
+    // saves the last recorded run under replays/, returning the file name
+    pub fn save_recording(&self) -> Option<String> {
+        let replay = self.recording.as_ref()?;
+        let file_name = format!("replays/{}-{}.rpl", now(), replay.seed);
+        match replay.save(&file_name) {
+            Ok(()) => Some(file_name),
+            Err(e) => {
+                println!("failed to save replay: {}", e);
+                None
+            }
+        }
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(PlayerInput::default())
+            .add_resource(Replays::default())
+            .add_system_to_stage(stage::PRE_UPDATE, start_replay_or_recording.system())
+            .add_system_to_stage(stage::PRE_UPDATE, sample_input.system());
+    }
+}
+
+fn start_replay_or_recording(
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    run_seed: Res<RunSeed>,
+    difficulty: Res<DifficultySettings>,
+    mut game_data: ResMut<GameData>,
+    mut replays: ResMut<Replays>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    replays.cursor = 0;
+    replays.step_budget = 0;
+    replays.playback = replays.pending.take();
+    game_data.replaying = replays.playback.is_some();
+    if !game_data.replaying {
+        replays.recording = Some(Replay::new(run_seed.seed, difficulty.current));
+    }
+}
+
+// runs in stage::PRE_UPDATE after accumulate_time and before the simulation stage, since a replay
+// also decides how many steps this frame simulates
+fn sample_input(
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    mut timestep: ResMut<FixedTimestep>,
+    mut player_input: ResMut<PlayerInput>,
+    mut replays: ResMut<Replays>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+) {
+    if game_data.game_state != GameState::Playing {
+        *player_input = PlayerInput::default();
+        return;
+    }
+
+    if replays.playback.is_some() {
+        let cursor = replays.cursor;
+        let frame = replays
+            .playback
+            .as_ref()
+            .and_then(|playback| playback.frames.get(cursor).copied());
+        replays.step_budget += timestep.steps;
+        timestep.steps = 0;
+        match frame {
+            Some(frame) if replays.step_budget < frame.steps as u32 => {}
+            Some(frame) => {
+                timestep.steps = frame.steps as u32;
+                *player_input = PlayerInput::from_bytes(frame.steer, frame.buttons);
+                replays.step_budget -= timestep.steps;
+                replays.cursor += 1;
+            }
+            None => {
+                // the run didn't end where it did when it was recorded
+                requests.send(GameStateRequest::MainMenu);
+            }
+        }
+        return;
+    }
+
+    // quantized the same way a replay stores it, so playing it back steers identically
+    let live = PlayerInput {
+        steer: actions.steer,
+        fire: actions.pressed(Action::Fire),
+        boost: actions.pressed(Action::Boost),
+        brake: actions.pressed(Action::Brake),
+    };
+    *player_input = PlayerInput::from_bytes(live.steer_byte(), live.buttons_byte());
+    if timestep.steps == 0 {
+        return;
+    }
+    let frame = ReplayFrame {
+        steps: timestep.steps as u8,
+        steer: player_input.steer_byte(),
+        buttons: player_input.buttons_byte(),
+    };
+    if let Some(recording) = &mut replays.recording {
+        recording.frames.push(frame);
+    }
+}
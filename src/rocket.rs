@@ -1,93 +1,290 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::gamedata::GameData;
+use crate::{
+    asteroids::AsteroidSpawner,
+    collision::Collider,
+    gamedata::GameData,
+    gamestate::NewRunEvent,
+    headless::load_or_placeholder,
+    replay::PlayerInput,
+    throttle::Throttle,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+    tuning::Tuning,
+};
+
+const PITCH_PER_SPEED_SCALE: f32 = 0.3; // radians the nose dips per unit of speed scale above 1
+const FLAME_Z: f32 = 1.3; // the tail of the rocket
+const FLAME_RADIUS: f32 = 0.25;
+const FLAME_LENGTH: f32 = 1.2; // at normal speed, it grows and shrinks with the speed scale
 
 pub struct Rocket;
 
+// the exhaust behind the rocket, a child of it so it turns with it
+struct Flame;
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RocketSpecs {
     pub steering_speed: f32,
     pub max_x_velocity: f32,
     pub max_steering_angle: f32,
 }
 
+impl RocketSpecs {
+    // how far sideways the rocket gets in the given time starting from straight ahead, turning as
+    // fast as it can. follows from RocketKinematics::step, where sideways speed grows with the heading
+    pub fn lateral_reach(&self, seconds: f32) -> f32 {
+        let turn_time = self.max_steering_angle / self.steering_speed; // until it's at full sideways speed
+        if seconds < turn_time {
+            0.5 * self.max_x_velocity * seconds * seconds / turn_time
+        } else {
+            self.max_x_velocity * (seconds - 0.5 * turn_time)
+        }
+    }
+}
+
+// how the rocket moves across the field. the rocket itself only ever turns, while the asteroid
+// field slides by -lateral_position, so this is the one place sideways motion is simulated
+#[derive(Default, Clone)]
+pub struct RocketKinematics {
+    pub heading: f32,          // the steering angle, positive is left
+    pub lateral_velocity: f32, // sideways speed along x, which is negative while heading left
+    pub lateral_position: f32, // how far the rocket is from the middle of the field along x
+    previous_heading: f32,          // heading one simulation step ago, for interpolation
+    previous_lateral_position: f32, // lateral_position one simulation step ago, for interpolation
+}
+
+impl RocketKinematics {
+    // advances one simulation step. steer is -1 (full right) to 1 (full left); the rocket turns
+    // towards that fraction of the max steering angle and drifts sideways in proportion to how
+    // far it's turned, stopping at max_lateral_position on either side
+    pub fn step(&mut self, specs: &RocketSpecs, steer: f32, max_lateral_position: f32, delta_seconds: f32) {
+        self.previous_heading = self.heading;
+        self.previous_lateral_position = self.lateral_position;
+
+        let target_heading = steer.max(-1.0).min(1.0) * specs.max_steering_angle;
+        let turn = delta_seconds * specs.steering_speed;
+        self.heading += (target_heading - self.heading).max(-turn).min(turn);
+
+        self.lateral_velocity = -self.heading / specs.max_steering_angle * specs.max_x_velocity;
+        self.lateral_position = (self.lateral_position + self.lateral_velocity * delta_seconds)
+            .max(-max_lateral_position)
+            .min(max_lateral_position);
+    }
+
+    // makes the last step the only state, for when nothing is simulated
+    pub fn settle(&mut self) {
+        self.previous_heading = self.heading;
+        self.previous_lateral_position = self.lateral_position;
+    }
+
+    pub fn interpolated_heading(&self, alpha: f32) -> f32 {
+        lerp(self.previous_heading, self.heading, alpha)
+    }
+
+    // where the asteroid field sits along x, opposite to the rocket
+    pub fn field_x(&self) -> f32 {
+        -self.lateral_position
+    }
+
+    pub fn interpolated_field_x(&self, alpha: f32) -> f32 {
+        -lerp(self.previous_lateral_position, self.lateral_position, alpha)
+    }
+}
+
 pub struct RocketPlugin;
 
 impl Plugin for RocketPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_resource(RocketSpecs {
-            max_x_velocity: 18.0,
-            steering_speed: 1.0,
-            max_steering_angle: 2.0 * 30.0 / 180.0,
-        })
+        // the tuning file replaces these once TuningPlugin has read it
+        app.add_resource(Tuning::default().rocket)
+        .add_resource(RocketKinematics::default())
         .add_startup_system(spawn_rocket.system())
-        .add_system(steer_rocket.system());
+        .add_system_to_stage(stage::PRE_UPDATE, reset_rocket.system())
+        .add_simulation_system(steer_rocket.system())
+        .add_system(interpolate_rocket.system())
+        .add_system(scale_flame.system());
+    }
+}
+
+fn reset_rocket(
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    mut kinematics: ResMut<RocketKinematics>,
+    mut rocket_query: Query<(&Rocket, &mut Transform)>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    *kinematics = RocketKinematics::default();
+    for (_rocket, mut transform) in &mut rocket_query.iter() {
+        transform.set_rotation(Quat::default());
     }
 }
 
 fn steer_rocket(
-    time: Res<Time>,
+    timestep: Res<FixedTimestep>,
     game_data: Res<GameData>,
-    keyboard_input: Res<Input<KeyCode>>,
+    player_input: Res<PlayerInput>,
     rocket_specs: Res<RocketSpecs>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    mut kinematics: ResMut<RocketKinematics>,
+) {
+    if game_data.game_state != crate::gamestate::GameState::Playing {
+        // nothing moves, so there's nothing to interpolate either
+        kinematics.settle();
+        return;
+    }
+
+    kinematics.step(&rocket_specs, player_input.steer, asteroid_spawner.max_x, timestep.step);
+}
+
+// turns the rocket with its heading and dips the nose while boosting, raising it while braking
+fn interpolate_rocket(
+    timestep: Res<FixedTimestep>,
+    kinematics: Res<RocketKinematics>,
+    throttle: Res<Throttle>,
     _rocket: &Rocket,
     mut transform: Mut<Transform>,
 ) {
-    match game_data.game_state {
-        crate::gamestate::GameState::Menu => return,
-        crate::gamestate::GameState::Playing => {}
-        crate::gamestate::GameState::Dead => return,
-    }
-
-    let axis_angle = transform.rotation().to_axis_angle();
-    let y_rotation = axis_angle.0.y() * axis_angle.1;
-    let mut angle = time.delta_seconds * rocket_specs.steering_speed;
-    if keyboard_input.pressed(KeyCode::A) {
-        if y_rotation >= rocket_specs.max_steering_angle {
-            return;
-        };
-    } else if keyboard_input.pressed(KeyCode::D) {
-        if y_rotation <= -rocket_specs.max_steering_angle {
-            return;
-        };
-        angle = -angle;
-    } else {
-        if y_rotation == 0.0 {
-            return;
-        }
-        if y_rotation.abs() < angle {
-            transform.set_rotation(Quat::default());
-            return;
-        }
-        if y_rotation > 0.0 {
-            angle = -angle;
-        }
-    }
-    transform.rotate(Quat::from_rotation_y(angle));
+    let pitch = -(throttle.interpolated_speed_scale(timestep.alpha) - 1.0) * PITCH_PER_SPEED_SCALE;
+    transform.set_rotation(
+        Quat::from_rotation_y(kinematics.interpolated_heading(timestep.alpha)) * Quat::from_rotation_x(pitch),
+    );
+}
+
+// stretches the flame backwards from the tail, so its front stays put
+fn scale_flame(timestep: Res<FixedTimestep>, throttle: Res<Throttle>, _flame: &Flame, mut transform: Mut<Transform>) {
+    let length = FLAME_LENGTH * throttle.interpolated_speed_scale(timestep.alpha);
+    *transform = flame_transform(length);
+}
+
+fn flame_transform(length: f32) -> Transform {
+    Transform::new(Mat4::from_scale_rotation_translation(
+        Vec3::new(FLAME_RADIUS, FLAME_RADIUS, length * 0.5),
+        Quat::identity(),
+        Vec3::new(0.0, 0.0, FLAME_Z + length * 0.5),
+    ))
 }
 
 fn spawn_rocket(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
     mut textures: ResMut<Assets<Texture>>,
 ) {
-    let texture_handle = asset_server
-        .load_sync(&mut textures, "assets/models/rocket/RocketColor.png")
-        .unwrap();
+    let texture_handle =
+        load_or_placeholder(&asset_server, &mut textures, "assets/models/rocket/RocketColor.png");
 
     let material_handle = materials.add(StandardMaterial {
         albedo_texture: Some(texture_handle),
         ..Default::default()
     });
 
+    let mesh_handle =
+        load_or_placeholder(&asset_server, &mut meshes, "assets/models/rocket/Rocket.glb");
+    let collider = meshes
+        .get(&mesh_handle)
+        .map_or(Collider { radius: 1.0 }, |mesh| Collider::from_mesh(mesh, 1.0));
+
     commands
         .spawn(PbrComponents {
-            mesh: asset_server
-                .load("assets/models/rocket/Rocket.glb")
-                .unwrap(),
+            mesh: mesh_handle,
             material: material_handle,
             transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
             ..Default::default()
         })
-        .with(Rocket);
+        .with(Rocket)
+        .with(collider)
+        .with_children(|parent| {
+            parent
+                .spawn(PbrComponents {
+                    mesh: meshes.add(Mesh::from(shape::Icosphere {
+                        radius: 1.0,
+                        subdivisions: 2,
+                    })),
+                    material: materials.add(StandardMaterial {
+                        albedo: Color::rgb(1.0, 0.55, 0.1),
+                        shaded: false,
+                        ..Default::default()
+                    }),
+                    transform: flame_transform(FLAME_LENGTH),
+                    ..Default::default()
+                })
+                .with(Flame);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 0.01;
+    const FIELD_EDGE: f32 = 100.0;
+
+    fn specs() -> RocketSpecs {
+        RocketSpecs {
+            steering_speed: 2.0,
+            max_x_velocity: 10.0,
+            max_steering_angle: 0.5,
+        }
+    }
+
+    fn fly(kinematics: &mut RocketKinematics, steer: f32, seconds: f32, max_lateral_position: f32) {
+        for _ in 0..(seconds / STEP).round() as u32 {
+            kinematics.step(&specs(), steer, max_lateral_position, STEP);
+        }
+    }
+
+    #[test]
+    fn turns_at_steering_speed_and_speeds_up_with_the_heading() {
+        let mut kinematics = RocketKinematics::default();
+        fly(&mut kinematics, 1.0, 0.1, FIELD_EDGE);
+        assert!((kinematics.heading - 0.2).abs() < 1e-4);
+        assert!((kinematics.lateral_velocity + 4.0).abs() < 1e-3);
+        assert!(kinematics.lateral_position < 0.0);
+    }
+
+    #[test]
+    fn travels_as_far_as_lateral_reach_says() {
+        for &seconds in &[0.1, 0.25, 1.0] {
+            let mut kinematics = RocketKinematics::default();
+            fly(&mut kinematics, -1.0, seconds, FIELD_EDGE);
+            let reach = specs().lateral_reach(seconds);
+            // each step moves at the speed it ends on, so stepping is ahead by less than a step at full speed
+            assert!(kinematics.lateral_position >= reach - 1e-4);
+            assert!(kinematics.lateral_position - reach < specs().max_x_velocity * STEP);
+        }
+    }
+
+    #[test]
+    fn heading_and_position_stop_at_their_limits() {
+        let mut kinematics = RocketKinematics::default();
+        fly(&mut kinematics, 3.0, 5.0, 20.0); // more than full steer
+        assert!((kinematics.heading - 0.5).abs() < 1e-6);
+        assert!((kinematics.lateral_velocity + 10.0).abs() < 1e-4);
+        assert_eq!(kinematics.lateral_position, -20.0);
+
+        fly(&mut kinematics, -3.0, 5.0, 20.0);
+        assert!((kinematics.heading + 0.5).abs() < 1e-6);
+        assert_eq!(kinematics.lateral_position, 20.0);
+    }
+
+    #[test]
+    fn straightens_out_when_let_go() {
+        let mut kinematics = RocketKinematics::default();
+        fly(&mut kinematics, 1.0, 1.0, FIELD_EDGE);
+        // turning back takes max_steering_angle / steering_speed
+        fly(&mut kinematics, 0.0, 0.25, FIELD_EDGE);
+        assert!(kinematics.heading.abs() < 1e-4);
+        assert!(kinematics.lateral_velocity.abs() < 1e-3);
+
+        // and then holds where it got to instead of drifting back to the middle
+        let position = kinematics.lateral_position;
+        fly(&mut kinematics, 0.0, 1.0, FIELD_EDGE);
+        assert_eq!(kinematics.heading, 0.0);
+        assert!((kinematics.lateral_position - position).abs() < 1e-4);
+    }
 }
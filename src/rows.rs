@@ -0,0 +1,278 @@
+use crate::{asteroids::AsteroidVariant, timestep::lerp};
+
+// lays out the field asteroids of a row. every row has a gap at least as wide as the rocket,
+// no further from the previous row's gap than the rocket can steer in the time between them,
+// and asteroids never overlap each other or the gap, not even the one that drifts sideways
+
+const GAP_MARGIN: f32 = 0.5; // room to spare on either side of the rocket in a gap
+const REACH_SAFETY: f32 = 0.75; // only ask for this much of what a perfect player could steer
+const DRIFT_CHANCE: f32 = 0.3; // how often a row has a drifting asteroid
+const MIN_DRIFT_SPEED: f32 = 1.0;
+const MAX_DRIFT_SPEED: f32 = 4.0;
+const MIN_DRIFT_RANGE: f32 = 2.0; // not worth drifting in less room than this
+
+pub struct RowAsteroid {
+    pub variant: usize, // index into RowParams::variants
+    pub x: f32,
+    pub z: f32,
+    pub scale: f32,
+    pub radius: f32, // the collider radius at this scale
+    pub drift: Option<RowDrift>,
+}
+
+// an asteroid moving back and forth between min_x and max_x
+pub struct RowDrift {
+    pub velocity: f32,
+    pub min_x: f32,
+    pub max_x: f32,
+}
+
+pub struct Row {
+    pub asteroids: Vec<RowAsteroid>,
+    pub gap_x: f32, // the middle of the gap the rocket can fly through
+}
+
+pub struct RowParams<'a> {
+    pub count: i32,                      // how many asteroids to try to fit
+    pub variants: &'a [AsteroidVariant], // what each asteroid picks from by weight
+    pub max_x: f32,                      // where the border asteroids are
+    pub border_radius: f32,
+    pub z_rand: f32,
+    pub min_x_spacing: f32, // the gap left between the colliders of two asteroids
+    pub rocket_radius: f32,
+    pub previous_gap_x: f32,
+    pub reach: f32, // how far sideways the rocket can get before this row arrives
+}
+
+pub fn generate_row(rng: &fastrand::Rng, params: &RowParams) -> Row {
+    let gap_half_width = params.rocket_radius + GAP_MARGIN;
+    let gap_limit = (params.max_x - params.border_radius - gap_half_width).max(0.0);
+    let reach = params.reach * REACH_SAFETY;
+    let gap_x = (params.previous_gap_x + (rng.f32() * 2.0 - 1.0) * reach)
+        .max(-gap_limit)
+        .min(gap_limit);
+
+    let mut asteroids: Vec<RowAsteroid> = vec![];
+    for _ in 0..params.count {
+        let variant = pick_variant(rng, params.variants);
+        let scale = params.variants[variant].random_scale(rng);
+        let radius = params.variants[variant].collider_radius * scale;
+        let z = rng.f32() * params.z_rand;
+        // kept as far from the border as from any other asteroid
+        let edge = params.max_x - params.border_radius - radius - params.min_x_spacing;
+
+        let mut blocked = vec![(gap_x - gap_half_width - radius, gap_x + gap_half_width + radius)];
+        for other in &asteroids {
+            let clearance = other.radius + radius + params.min_x_spacing;
+            blocked.push((other.x - clearance, other.x + clearance));
+        }
+        // a full row just ends up with fewer asteroids
+        if let Some(x) = sample_free(rng, -edge, edge, &mut blocked) {
+            asteroids.push(RowAsteroid {
+                variant,
+                x,
+                z,
+                scale,
+                radius,
+                drift: None,
+            });
+        }
+    }
+
+    // at most one asteroid per row drifts, through the room between its neighbours, the gap
+    // and the border. only the asteroids that stay put are in its way, so that room never changes
+    if !asteroids.is_empty() && rng.f32() < DRIFT_CHANCE {
+        let index = rng.usize(..asteroids.len());
+        let velocity = lerp(MIN_DRIFT_SPEED, MAX_DRIFT_SPEED, rng.f32()) * if rng.bool() { 1.0 } else { -1.0 };
+        let asteroid = &asteroids[index];
+        let edge = params.max_x - params.border_radius - asteroid.radius - params.min_x_spacing;
+        let mut min_x = -edge;
+        let mut max_x = edge;
+        let mut blocked = vec![(gap_x - gap_half_width - asteroid.radius, gap_x + gap_half_width + asteroid.radius)];
+        for (other_index, other) in asteroids.iter().enumerate() {
+            if other_index != index {
+                let clearance = other.radius + asteroid.radius + params.min_x_spacing;
+                blocked.push((other.x - clearance, other.x + clearance));
+            }
+        }
+        for (blocked_start, blocked_end) in blocked {
+            if blocked_end <= asteroid.x {
+                min_x = min_x.max(blocked_end);
+            } else if blocked_start >= asteroid.x {
+                max_x = max_x.min(blocked_start);
+            }
+        }
+        if min_x <= asteroid.x && asteroid.x <= max_x && max_x - min_x >= MIN_DRIFT_RANGE {
+            asteroids[index].drift = Some(RowDrift { velocity, min_x, max_x });
+        }
+    }
+
+    Row { asteroids, gap_x }
+}
+
+// picks an index into variants, which can't be empty, each with a chance proportional to its weight
+pub fn pick_variant(rng: &fastrand::Rng, variants: &[AsteroidVariant]) -> usize {
+    let total: f32 = variants.iter().map(|variant| variant.weight).sum();
+    let mut roll = rng.f32() * total;
+    for (index, variant) in variants.iter().enumerate() {
+        if roll < variant.weight {
+            return index;
+        }
+        roll -= variant.weight;
+    }
+    variants.len() - 1 // only reached through rounding
+}
+
+// picks a uniformly random point in min..max outside all blocked ranges, if there is room left
+fn sample_free(rng: &fastrand::Rng, min: f32, max: f32, blocked: &mut [(f32, f32)]) -> Option<f32> {
+    blocked.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    let mut free = vec![];
+    let mut start = min;
+    for (blocked_start, blocked_end) in blocked.iter() {
+        if *blocked_start > start {
+            free.push((start, blocked_start.min(max)));
+        }
+        start = start.max(*blocked_end);
+        if start >= max {
+            break;
+        }
+    }
+    if start < max {
+        free.push((start, max));
+    }
+    free.retain(|(start, end)| end > start);
+
+    let total: f32 = free.iter().map(|(start, end)| end - start).sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut offset = rng.f32() * total;
+    for (start, end) in &free {
+        if offset <= end - start {
+            return Some(start + offset);
+        }
+        offset -= end - start;
+    }
+    free.last().map(|(_start, end)| *end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::Handle;
+
+    const SEEDS: u64 = 5000;
+    const EPSILON: f32 = 1e-4;
+
+    fn variant(collider_radius: f32, weight: f32) -> AsteroidVariant {
+        AsteroidVariant {
+            mesh: Handle::default(),
+            material: Handle::default(),
+            collider_radius,
+            min_scale: 0.75,
+            max_scale: 1.0,
+            weight,
+        }
+    }
+
+    // the default tuning, with a bit of everything varied per seed
+    fn random_params<'a>(rng: &fastrand::Rng, variants: &'a [AsteroidVariant]) -> RowParams<'a> {
+        let max_x = 12.0;
+        let border_radius = 2.0;
+        let rocket_radius = 1.0;
+        let gap_limit = max_x - border_radius - rocket_radius - GAP_MARGIN;
+        RowParams {
+            count: rng.i32(0..=6),
+            variants,
+            max_x,
+            border_radius,
+            z_rand: 15.0,
+            min_x_spacing: 2.0,
+            rocket_radius,
+            previous_gap_x: (rng.f32() * 2.0 - 1.0) * gap_limit,
+            reach: rng.f32() * 10.0,
+        }
+    }
+
+    // every x an asteroid can be at, whether it drifts or not
+    fn x_range(asteroid: &RowAsteroid) -> (f32, f32) {
+        match &asteroid.drift {
+            Some(drift) => (drift.min_x, drift.max_x),
+            None => (asteroid.x, asteroid.x),
+        }
+    }
+
+    fn check_row(seed: u64) {
+        let variants = [variant(1.5, 1.0), variant(2.0, 2.0), variant(1.0, 1.0)];
+        let rng = fastrand::Rng::with_seed(seed);
+        let params = random_params(&rng, &variants);
+        let row = generate_row(&rng, &params);
+
+        let gap_half_width = params.rocket_radius + GAP_MARGIN;
+        let edge = params.max_x - params.border_radius;
+        assert!(row.gap_x.abs() + gap_half_width <= edge + EPSILON, "seed {}: gap in the border", seed);
+        assert!(
+            (row.gap_x - params.previous_gap_x).abs() <= params.reach + EPSILON,
+            "seed {}: gap out of reach",
+            seed
+        );
+
+        for (index, asteroid) in row.asteroids.iter().enumerate() {
+            let (min_x, max_x) = x_range(asteroid);
+            assert!(min_x <= asteroid.x && asteroid.x <= max_x, "seed {}: starts outside its drift", seed);
+            assert!(0.0 <= asteroid.z && asteroid.z <= params.z_rand, "seed {}: outside the row", seed);
+            assert!(
+                -edge + asteroid.radius <= min_x + EPSILON && max_x + asteroid.radius <= edge + EPSILON,
+                "seed {}: asteroid in the border",
+                seed
+            );
+            let gap_clearance = gap_half_width + asteroid.radius;
+            assert!(
+                max_x <= row.gap_x - gap_clearance + EPSILON || min_x >= row.gap_x + gap_clearance - EPSILON,
+                "seed {}: asteroid in the gap",
+                seed
+            );
+
+            for other in &row.asteroids[index + 1..] {
+                let (other_min_x, other_max_x) = x_range(other);
+                let clearance = asteroid.radius + other.radius + params.min_x_spacing;
+                assert!(
+                    max_x + clearance <= other_min_x + EPSILON || other_max_x + clearance <= min_x + EPSILON,
+                    "seed {}: asteroids overlap",
+                    seed
+                );
+            }
+        }
+        assert!(row.asteroids.iter().filter(|asteroid| asteroid.drift.is_some()).count() <= 1);
+    }
+
+    #[test]
+    fn rows_keep_their_promises() {
+        for seed in 0..SEEDS {
+            check_row(seed);
+        }
+    }
+
+    #[test]
+    fn no_reach_keeps_the_gap_in_place() {
+        let variants = [variant(2.0, 1.0)];
+        for seed in 0..SEEDS {
+            let rng = fastrand::Rng::with_seed(seed);
+            let params = RowParams {
+                reach: 0.0,
+                previous_gap_x: 3.0,
+                ..random_params(&rng, &variants)
+            };
+            assert_eq!(generate_row(&rng, &params).gap_x, 3.0);
+        }
+    }
+
+    #[test]
+    fn weights_decide_how_often_variants_are_picked() {
+        let variants = [variant(1.0, 1.0), variant(1.0, 3.0)];
+        let rng = fastrand::Rng::with_seed(0);
+        let picks = 10000;
+        let heavy = (0..picks).filter(|_| pick_variant(&rng, &variants) == 1).count();
+        assert!((heavy as f32 / picks as f32 - 0.75).abs() < 0.03);
+    }
+}
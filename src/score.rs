@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::{slot_offset, Asteroid, AsteroidGroup, AsteroidSlot, AsteroidSpawner, Drift},
+    collision::Collider,
+    gamedata::GameData,
+    gamestate::GameState,
+    pickups::PowerUps,
+    rocket::{Rocket, RocketKinematics},
+    throttle::Throttle,
+    timestep::{AddSimulationSystem, FixedTimestep},
+};
+
+const DISTANCE_PER_POINT: f32 = 10.0;
+const CLOSE_PASS_DISTANCE: f32 = 1.5; // the max gap between colliders that still counts as a close pass
+const CLOSE_PASS_BONUS: i32 = 25;
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_simulation_system(close_pass_bonus.system())
+            .add_simulation_system(update_score.system());
+    }
+}
+
+// distance covered while boosting counts for more and while braking for less. points gained while
+// a score multiplier runs are added again to the bonus for every extra multiple
+fn update_score(
+    mut game_data: ResMut<GameData>,
+    timestep: Res<FixedTimestep>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    power_ups: Res<PowerUps>,
+    throttle: Res<Throttle>,
+) {
+    match game_data.game_state {
+        GameState::Menu => return,
+        GameState::Playing => {}
+        GameState::Paused => return,
+        GameState::Dead => return,
+        GameState::Controls => return,
+    }
+
+    let distance = asteroid_spawner.step_distance(timestep.step);
+    game_data.distance_score += distance / DISTANCE_PER_POINT * throttle.speed_scale;
+    let distance_points = game_data.distance_score as i32;
+    let gained = distance_points + game_data.bonus - game_data.score;
+    game_data.bonus += gained * (power_ups.score_multiplier() - 1);
+    game_data.score = distance_points + game_data.bonus;
+}
+
+// awards a bonus for every asteroid that passes the rocket within CLOSE_PASS_DISTANCE, measured
+// at the step it draws level with the rocket
+fn close_pass_bonus(
+    mut game_data: ResMut<GameData>,
+    kinematics: Res<RocketKinematics>,
+    mut rocket_query: Query<(&Rocket, &Transform, &Collider)>,
+    mut asteroid_group_query: Query<(&AsteroidGroup, &Children)>,
+    mut asteroid_query: Query<(&mut Asteroid, &AsteroidSlot, &Collider, &Transform, &Drift)>,
+) {
+    match game_data.game_state {
+        GameState::Menu => return,
+        GameState::Playing => {}
+        GameState::Paused => return,
+        GameState::Dead => return,
+        GameState::Controls => return,
+    }
+
+    for (_rocket, rocket_transform, rocket_collider) in &mut rocket_query.iter() {
+        let rocket_position = rocket_transform.translation();
+        for (asteroid_group, children) in &mut asteroid_group_query.iter() {
+            if !asteroid_group.active {
+                continue;
+            }
+            let group_position = asteroid_group.position(&kinematics);
+            for child in children.iter() {
+                match asteroid_query.get::<AsteroidSlot>(*child) {
+                    Ok(slot) if slot.in_use => {}
+                    _ => continue,
+                }
+                let mut asteroid = match asteroid_query.get_mut::<Asteroid>(*child) {
+                    Ok(asteroid) => asteroid,
+                    Err(_) => continue,
+                };
+                if asteroid.passed {
+                    continue;
+                }
+                let position = match (asteroid_query.get::<Transform>(*child), asteroid_query.get::<Drift>(*child)) {
+                    (Ok(transform), Ok(drift)) => group_position + slot_offset(&transform, Some(&*drift)),
+                    _ => continue,
+                };
+                if position.z() < rocket_position.z() {
+                    continue;
+                }
+                asteroid.passed = true;
+
+                let radius = match asteroid_query.get::<Collider>(*child) {
+                    Ok(collider) => collider.radius,
+                    Err(_) => continue,
+                };
+                let gap = (position.x() - rocket_position.x()).abs() - radius - rocket_collider.radius;
+                if gap >= 0.0 && gap < CLOSE_PASS_DISTANCE {
+                    game_data.bonus += CLOSE_PASS_BONUS;
+                }
+            }
+        }
+    }
+}
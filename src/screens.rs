@@ -2,13 +2,23 @@ use bevy::prelude::*;
 
 use assets::ScreenAssets;
 
-use self::{components::fps_counter::FpsCounterPlugin, menu::MenuPlugin};
+use self::{
+    components::fps_counter::FpsCounterPlugin, controls::ControlsPlugin, game_over::GameOverPlugin,
+    hud::HudPlugin, menu::MenuPlugin, pause::PausePlugin,
+};
+pub mod controls;
+pub mod game_over;
+pub mod hud;
 pub mod menu;
+pub mod pause;
 pub mod components;
 pub mod assets;
 
 pub struct ScreensPlugin;
 pub struct Menu;
+pub struct GameOver;
+pub struct PauseMenu;
+pub struct ControlsMenu;
 
 
 impl Plugin for ScreensPlugin {
@@ -18,6 +28,10 @@ impl Plugin for ScreensPlugin {
             default_font: Handle::default(),
         })
         .add_plugin(MenuPlugin)
+        .add_plugin(GameOverPlugin)
+        .add_plugin(PausePlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(HudPlugin)
         .add_plugin(FpsCounterPlugin);
     }
 }
@@ -1 +1,2 @@
 pub mod fps_counter;
+pub mod option_list;
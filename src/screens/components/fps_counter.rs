@@ -1,6 +1,6 @@
 use bevy::{diagnostic::Diagnostics, diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
 
-use crate::screens::assets::ScreenAssets;
+use crate::{diagnostics::GameDiagnosticsPlugin, screens::assets::ScreenAssets};
 
 pub struct FpsCounterPlugin;
 struct FpsCounter;
@@ -38,9 +38,14 @@ fn setup(mut commands: Commands, screen_assets: Res<ScreenAssets>) {
 }
 
 fn fps_counter(diagnostics: Res<Diagnostics>, mut text: Mut<Text>, _fps_counter: &FpsCounter) {
-    if let Some(fps_diagnostic) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
-        if let Some(fps) = fps_diagnostic.value() {
-            text.value = format!("{:.1} fps", fps);
-        }
+    let value = |id| diagnostics.get(id).and_then(|diagnostic| diagnostic.value());
+    if let Some(fps) = value(FrameTimeDiagnosticsPlugin::FPS) {
+        text.value = format!(
+            "{:.1} fps, {} asteroids, {} meshes, {} materials",
+            fps,
+            value(GameDiagnosticsPlugin::LIVE_ASTEROIDS).unwrap_or(0.0),
+            value(GameDiagnosticsPlugin::MESHES).unwrap_or(0.0),
+            value(GameDiagnosticsPlugin::MATERIALS).unwrap_or(0.0)
+        );
     }
 }
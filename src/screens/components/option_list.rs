@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::controls::{Action, Actions};
+
+// how long a screen that comes up mid-run ignores Confirm. fire shares its keys and buttons, so a
+// shot just before crashing or pausing would otherwise pick an option straight away
+pub const CONFIRM_DELAY: f32 = 0.5;
+
+// a selectable entry in a screen's list of options
+pub struct OptionItem {
+    pub index: usize,
+}
+
+pub fn option_color(is_selected: bool) -> Color {
+    if is_selected {
+        Color::rgb(1.0, 0.8, 0.2)
+    } else {
+        Color::WHITE
+    }
+}
+
+pub fn option_text(font: Handle<Font>, label: &str) -> TextComponents {
+    TextComponents {
+        style: Style {
+            margin: Rect::all(Val::Px(5.0)),
+            ..Default::default()
+        },
+        text: Text {
+            value: label.into(),
+            font,
+            style: TextStyle {
+                font_size: 30.0,
+                color: Color::WHITE,
+            },
+        },
+        ..Default::default()
+    }
+}
+
+// moves the selection with the menu up/down actions, wrapping around.
+// returns true when the selected option is confirmed
+pub fn navigate(actions: &Actions, selected: &mut usize, count: usize) -> bool {
+    if count == 0 {
+        return false;
+    }
+    if actions.just_pressed(Action::MenuUp) {
+        *selected = (*selected + count - 1) % count;
+    }
+    if actions.just_pressed(Action::MenuDown) {
+        *selected = (*selected + 1) % count;
+    }
+    actions.just_pressed(Action::Confirm)
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::{Action, Actions, Controls, ACTIONS, BINDABLE_KEYS},
+    gamedata::GameData,
+    gamestate::{GameState, GameStateRequest},
+};
+
+use super::{
+    assets::ScreenAssets,
+    components::option_list::{navigate, option_color, option_text, OptionItem},
+    ControlsMenu,
+};
+
+// options listed after one row per action
+const RESET_OPTION: usize = ACTIONS.len();
+const BACK_OPTION: usize = ACTIONS.len() + 1;
+const OPTION_COUNT: usize = ACTIONS.len() + 2;
+
+pub struct ControlsPlugin;
+
+#[derive(Default)]
+struct ControlsScreen {
+    selected: usize,
+    rebinding: Option<Action>, // the action waiting for a key press to be bound to it
+}
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(ControlsScreen::default())
+            .add_startup_system(setup.system())
+            .add_system(controls_visibility.system())
+            .add_system(controls_input.system())
+            .add_system(update_bindings_text.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, screen_assets: Res<ScreenAssets>) {
+    let font = screen_assets.default_font;
+    commands.spawn(NodeComponents {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+        draw: Draw {
+            is_visible: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    }).with(ControlsMenu).with_children(|parent| {
+        parent.spawn(TextComponents {
+            style: Style {
+                margin: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            text: Text {
+                value: "Controls".into(),
+                font,
+                style: TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            },
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }).with(ControlsMenu);
+
+        for index in 0..OPTION_COUNT {
+            let mut option = option_text(font, "");
+            option.text.style.font_size = 24.0;
+            option.draw.is_visible = false;
+            parent.spawn(option).with(ControlsMenu).with(OptionItem { index });
+        }
+    });
+}
+
+fn controls_visibility(game_data: Res<GameData>, _controls_menu: &ControlsMenu, mut draw: Mut<Draw>) {
+    draw.is_visible = game_data.game_state == GameState::Controls;
+}
+
+// confirming an action waits for the next key and makes it the only one bound to that action.
+// only the keyboard can do that, since a pad couldn't press a key to leave it again
+fn controls_input(
+    game_data: Res<GameData>,
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    mut controls: ResMut<Controls>,
+    mut screen: ResMut<ControlsScreen>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+) {
+    if game_data.game_state != GameState::Controls {
+        *screen = ControlsScreen::default();
+        return;
+    }
+
+    if let Some(action) = screen.rebinding {
+        let key_code = keyboard_input
+            .get_just_pressed()
+            .copied()
+            .find(|key_code| BINDABLE_KEYS.contains(key_code));
+        if let Some(key_code) = key_code {
+            controls.set_keys(action, vec![key_code]);
+            controls.save();
+            screen.rebinding = None;
+        }
+        return;
+    }
+
+    if navigate(&actions, &mut screen.selected, OPTION_COUNT) {
+        match screen.selected {
+            RESET_OPTION => {
+                *controls = Controls::default();
+                controls.save();
+            }
+            BACK_OPTION => requests.send(GameStateRequest::MainMenu),
+            _ if actions.gamepad_just_pressed(Action::Confirm) => {}
+            action => screen.rebinding = Some(ACTIONS[action]),
+        }
+    }
+}
+
+fn update_bindings_text(
+    game_data: Res<GameData>,
+    controls: Res<Controls>,
+    screen: Res<ControlsScreen>,
+    mut option_query: Query<(&ControlsMenu, &OptionItem, &mut Text)>,
+) {
+    if game_data.game_state != GameState::Controls {
+        return;
+    }
+
+    for (_controls_menu, option, mut text) in &mut option_query.iter() {
+        text.style.color = option_color(option.index == screen.selected);
+        text.value = match option.index {
+            RESET_OPTION => "Reset to defaults".into(),
+            BACK_OPTION => "Back".into(),
+            index => {
+                let action = ACTIONS[index];
+                if screen.rebinding == Some(action) {
+                    format!("{}: press a key", action.label())
+                } else {
+                    format!("{}: {}", action.label(), controls.describe(action))
+                }
+            }
+        };
+    }
+}
@@ -0,0 +1,250 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+
+use crate::{
+    asteroids::AsteroidSpawner,
+    controls::{Action, Actions},
+    gamedata::{GameData, RunSeed},
+    gamestate::{GameState, GameStateRequest},
+    highscores::{now, HighScoreEntry, HighScores, MAX_NAME_LENGTH},
+    replay::Replays,
+};
+
+use super::{
+    assets::ScreenAssets,
+    components::option_list::{navigate, option_color, option_text, OptionItem, CONFIRM_DELAY},
+    GameOver,
+};
+
+const OPTIONS: &[&str] = &["Retry", "Main menu"];
+
+pub struct GameOverPlugin;
+
+// which value of the finished run a summary text displays
+enum GameOverField {
+    Score,
+    Distance,
+    Duration,
+    BestScore,
+    Seed,
+}
+
+// shown instead of the options while the player types a name for a new high score
+struct NamePrompt;
+
+#[derive(Default)]
+struct NameEntry {
+    checked: bool, // whether this death has been checked against the high scores yet
+    active: bool,
+    submitted: bool, // the name was submitted this frame
+    name: String,
+    shown_for: f32, // seconds since the game over screen came up
+}
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(NameEntry::default())
+            .add_startup_system(setup.system())
+            .add_system(game_over_visibility.system())
+            .add_system(update_summary.system())
+            .add_system(name_entry_input.system())
+            .add_system(update_name_prompt.system())
+            .add_system(game_over_input.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, screen_assets: Res<ScreenAssets>) {
+    let font = screen_assets.default_font;
+    let summary_text = |value: &str| TextComponents {
+        style: Style {
+            margin: Rect::all(Val::Px(5.0)),
+            ..Default::default()
+        },
+        text: Text {
+            value: value.into(),
+            font,
+            style: TextStyle {
+                font_size: 24.0,
+                color: Color::WHITE,
+            },
+        },
+        draw: Draw {
+            is_visible: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    commands.spawn(NodeComponents {
+        style: Style {
+            margin: Rect::all(Val::Auto),
+            padding: Rect::all(Val::Px(20.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.4).into()),
+        draw: Draw {
+            is_visible: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    }).with(GameOver).with_children(|parent| {
+        parent.spawn(TextComponents {
+            style: Style {
+                margin: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            text: Text {
+                value: "Game over".into(),
+                font,
+                style: TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            },
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }).with(GameOver);
+
+        let fields = vec![
+            GameOverField::Score,
+            GameOverField::Distance,
+            GameOverField::Duration,
+            GameOverField::BestScore,
+            GameOverField::Seed,
+        ];
+        for field in fields {
+            parent.spawn(summary_text("")).with(GameOver).with(field);
+        }
+
+        parent.spawn(summary_text("")).with(NamePrompt);
+
+        for (index, label) in OPTIONS.iter().enumerate() {
+            let mut option = option_text(font, label);
+            option.draw.is_visible = false;
+            parent.spawn(option).with(GameOver).with(OptionItem { index });
+        }
+    });
+}
+
+fn game_over_visibility(game_data: Res<GameData>, _game_over: &GameOver, mut draw: Mut<Draw>) {
+    draw.is_visible = game_data.game_state == GameState::Dead;
+}
+
+fn update_summary(
+    game_data: Res<GameData>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    run_seed: Res<RunSeed>,
+    field: &GameOverField,
+    mut text: Mut<Text>,
+) {
+    if game_data.game_state != GameState::Dead {
+        return;
+    }
+
+    text.value = match field {
+        GameOverField::Score => format!("Score: {}", game_data.score),
+        GameOverField::Distance => format!("Distance: {:.0} m", asteroid_spawner.distance_traveled),
+        GameOverField::Duration => {
+            let seconds = game_data.run_time as u32;
+            format!("Time: {}:{:02}", seconds / 60, seconds % 60)
+        }
+        GameOverField::BestScore => format!("Best: {}", game_data.best_score),
+        GameOverField::Seed => format!("Seed: {}", run_seed.seed),
+    };
+}
+
+fn name_entry_input(
+    time: Res<Time>,
+    game_data: Res<GameData>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    run_seed: Res<RunSeed>,
+    replays: Res<Replays>,
+    mut high_scores: ResMut<HighScores>,
+    mut name_entry: ResMut<NameEntry>,
+    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Actions>,
+    mut character_reader: Local<EventReader<ReceivedCharacter>>,
+    character_events: Res<Events<ReceivedCharacter>>,
+) {
+    let characters: Vec<char> = character_reader
+        .iter(&character_events)
+        .map(|event| event.char)
+        .collect();
+
+    name_entry.submitted = false;
+    if game_data.game_state != GameState::Dead {
+        *name_entry = NameEntry::default();
+        return;
+    }
+    name_entry.shown_for += time.delta_seconds;
+    if !name_entry.checked {
+        name_entry.checked = true;
+        name_entry.active = !game_data.replaying && high_scores.qualifies(game_data.score);
+    } else if name_entry.active {
+        for c in characters {
+            if (c.is_alphanumeric() || c == ' ') && name_entry.name.chars().count() < MAX_NAME_LENGTH {
+                name_entry.name.push(c);
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Back) {
+            name_entry.name.pop();
+        }
+        // a pad can't type, but it can still submit. its confirm is also fire, so not right away
+        let pad_submit = actions.gamepad_just_pressed(Action::Confirm) && name_entry.shown_for >= CONFIRM_DELAY;
+        if keyboard_input.just_pressed(KeyCode::Return) || pad_submit {
+            let name = name_entry.name.trim();
+            high_scores.insert(HighScoreEntry {
+                name: if name.is_empty() { "???".into() } else { name.into() },
+                score: game_data.score,
+                distance: asteroid_spawner.distance_traveled,
+                seed: Some(run_seed.seed),
+                date: now(),
+                replay: replays.save_recording(),
+                difficulty: replays.recording.as_ref().map(|recording| recording.difficulty),
+            });
+            high_scores.save();
+            name_entry.active = false;
+            name_entry.submitted = true;
+        }
+    }
+}
+
+fn update_name_prompt(name_entry: Res<NameEntry>, mut prompt_query: Query<(&NamePrompt, &mut Text, &mut Draw)>) {
+    for (_name_prompt, mut text, mut draw) in &mut prompt_query.iter() {
+        draw.is_visible = name_entry.active;
+        text.value = format!("New high score! Name: {}_", name_entry.name);
+    }
+}
+
+fn game_over_input(
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    name_entry: Res<NameEntry>,
+    mut selected: Local<usize>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+    mut option_query: Query<(&GameOver, &OptionItem, &mut Text)>,
+) {
+    if game_data.game_state != GameState::Dead {
+        *selected = 0;
+        return;
+    }
+    // the enter that submits a name shouldn't also pick an option
+    if name_entry.active || name_entry.submitted {
+        return;
+    }
+
+    if navigate(&actions, &mut selected, OPTIONS.len()) && name_entry.shown_for >= CONFIRM_DELAY {
+        match *selected {
+            0 => requests.send(GameStateRequest::NewRun),
+            _ => requests.send(GameStateRequest::MainMenu),
+        }
+    }
+
+    for (_game_over, option, mut text) in &mut option_query.iter() {
+        text.style.color = option_color(option.index == *selected);
+    }
+}
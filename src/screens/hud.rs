@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::AsteroidSpawner,
+    difficulty::DifficultySettings,
+    gamedata::{GameData, RunSeed},
+    gamestate::GameState,
+    lives::Lives,
+    pickups::{PickupKind, PowerUps, PICKUP_KINDS},
+    throttle::Throttle,
+    weapon::Weapon,
+};
+
+use super::assets::ScreenAssets;
+
+pub struct HudPlugin;
+
+// which value a hud text displays
+enum HudField {
+    Score,
+    Distance,
+    Speed,
+    Seed,
+    Difficulty,
+    Heat,
+    Energy,
+    Lives,
+    Shield,
+}
+
+// a colored square and a text for one timed power-up, listed bottom up while it's active
+struct PowerUpIcon {
+    kind: PickupKind,
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup.system())
+            .add_system(update_hud.system())
+            .add_system(update_power_up_icons.system())
+            .add_system(update_power_up_texts.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, screen_assets: Res<ScreenAssets>) {
+    let fields = vec![
+        HudField::Score,
+        HudField::Distance,
+        HudField::Speed,
+        HudField::Seed,
+        HudField::Difficulty,
+        HudField::Heat,
+        HudField::Energy,
+        HudField::Lives,
+        HudField::Shield,
+    ];
+    for (i, field) in fields.into_iter().enumerate() {
+        commands
+            .spawn(TextComponents {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        top: Val::Px(10.0 + i as f32 * 24.0),
+                        left: Val::Px(10.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    value: "".into(),
+                    font: screen_assets.default_font,
+                    style: TextStyle {
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                },
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(field);
+    }
+
+    // shield charges and lives have their own fields
+    let timed_kinds = PICKUP_KINDS.iter().filter(|kind| kind.timed());
+    for (i, kind) in timed_kinds.enumerate() {
+        let bottom = Val::Px(10.0 + i as f32 * 26.0);
+        commands
+            .spawn(NodeComponents {
+                style: Style {
+                    size: Size::new(Val::Px(18.0), Val::Px(18.0)),
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        bottom,
+                        left: Val::Px(10.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                material: materials.add(kind.color().into()),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(PowerUpIcon { kind: *kind })
+            .spawn(TextComponents {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        bottom,
+                        left: Val::Px(36.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text {
+                    value: "".into(),
+                    font: screen_assets.default_font,
+                    style: TextStyle {
+                        font_size: 18.0,
+                        color: kind.color(),
+                    },
+                },
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(PowerUpIcon { kind: *kind });
+    }
+}
+
+// both the square and the text, shown while the power-up lasts
+fn update_power_up_icons(game_data: Res<GameData>, power_ups: Res<PowerUps>, icon: &PowerUpIcon, mut draw: Mut<Draw>) {
+    draw.is_visible = game_data.game_state != GameState::Menu
+        && game_data.game_state != GameState::Controls
+        && power_ups.remaining(icon.kind) > 0.0;
+}
+
+fn update_power_up_texts(power_ups: Res<PowerUps>, icon: &PowerUpIcon, mut text: Mut<Text>) {
+    text.value = format!("{} {:.1}s", icon.kind.label(), power_ups.remaining(icon.kind));
+}
+
+fn update_hud(
+    game_data: Res<GameData>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    run_seed: Res<RunSeed>,
+    difficulty: Res<DifficultySettings>,
+    weapon: Res<Weapon>,
+    lives: Res<Lives>,
+    throttle: Res<Throttle>,
+    field: &HudField,
+    mut text: Mut<Text>,
+    mut draw: Mut<Draw>,
+) {
+    draw.is_visible = game_data.game_state != GameState::Menu && game_data.game_state != GameState::Controls;
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    text.value = match field {
+        HudField::Score => format!("Score: {}", game_data.score),
+        HudField::Distance => format!("Distance: {:.0} m", asteroid_spawner.distance_traveled),
+        HudField::Speed => format!("Speed: {:.0} m/s", asteroid_spawner.z_velocity),
+        HudField::Seed => format!("Seed: {}", run_seed.seed),
+        HudField::Difficulty => format!("Difficulty: {}", difficulty.current.label()),
+        HudField::Heat if weapon.overheated => "Laser: overheated".into(),
+        HudField::Heat => format!("Laser heat: {:.0}%", weapon.heat * 100.0),
+        HudField::Energy if throttle.exhausted => "Boost: recharging".into(),
+        HudField::Energy => format!("Boost energy: {:.0}%", throttle.energy * 100.0),
+        HudField::Lives => format!("Lives: {}", lives.lives),
+        HudField::Shield => format!("Shield: {}", lives.shield_charges),
+    };
+}
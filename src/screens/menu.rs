@@ -1,19 +1,65 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::ReceivedCharacter};
 
-use super::{Menu, assets::ScreenAssets};
+use crate::{
+    controls::{Action, Actions, Controls},
+    difficulty::{DifficultySettings, DIFFICULTIES},
+    gamedata::{GameData, RunSeed},
+    gamestate::{GameState, GameStateRequest},
+    highscores::{format_date, HighScores, MAX_ENTRIES},
+    replay::{Replay, Replays},
+};
+
+use super::{Menu, assets::ScreenAssets, components::option_list::option_color};
 
 pub struct MenuPlugin;
 
+// a line of the high score table, 0 being the best run
+struct HighScoreRow(usize);
+struct SeedText;
+struct StartHint;
+struct DifficultyText;
+
+// the high score row whose replay the watch replay action plays back
+#[derive(Default)]
+struct SelectedHighScore(usize);
+
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(setup.system());
+        app.add_resource(SelectedHighScore::default())
+            .add_startup_system(setup.system())
+            .add_system(open_controls_input.system())
+            .add_system(high_score_replay_input.system())
+            .add_system(update_start_hint.system())
+            .add_system(difficulty_input.system())
+            .add_system(update_high_score_table.system())
+            .add_system(seed_entry_input.system());
     }
 }
 
 fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, screen_assets: Res<ScreenAssets>) {
+    let font = screen_assets.default_font;
+    let text = |value: &str, font_size: f32| TextComponents {
+        style: Style {
+            margin: Rect::all(Val::Px(4.0)),
+            ..Default::default()
+        },
+        text: Text {
+            value: value.into(),
+            font,
+            style: TextStyle {
+                font_size,
+                color: Color::WHITE,
+            },
+        },
+        ..Default::default()
+    };
+
     commands.spawn(NodeComponents {
         style: Style {
             margin: Rect::all(Val::Auto),
+            padding: Rect::all(Val::Px(10.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            align_items: AlignItems::Center,
             ..Default::default()
         },
         material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.4).into()),
@@ -26,14 +72,194 @@ fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, s
                 ..Default::default()
             },
             text: Text {
-                value: "Press space to begin".into(),
-                font: screen_assets.default_font,
+                value: "".into(),
+                font,
                 style: TextStyle {
                     font_size: 30.0,
                     color: Color::WHITE,
                 },
             },
             ..Default::default()
-        }).with(Menu);
+        }).with(Menu).with(StartHint);
+
+        parent.spawn(text("", 18.0)).with(Menu).with(DifficultyText);
+        parent.spawn(text("", 18.0)).with(Menu).with(SeedText);
+
+        parent.spawn(text("High scores", 24.0)).with(Menu);
+        for row in 0..MAX_ENTRIES {
+            parent.spawn(text("", 18.0)).with(Menu).with(HighScoreRow(row));
+        }
     });
 }
+
+// the keys are whatever the player bound, so the hint is rebuilt from the bindings
+fn update_start_hint(game_data: Res<GameData>, controls: Res<Controls>, _start_hint: &StartHint, mut text: Mut<Text>) {
+    if game_data.game_state != GameState::Menu {
+        return;
+    }
+
+    text.value = format!(
+        "Press {} to begin, {} for controls, {} to watch a high score's replay",
+        controls.describe(Action::Confirm),
+        controls.describe(Action::OpenControls),
+        controls.describe(Action::WatchReplay)
+    );
+}
+
+// steering left and right on the menu cycles through the presets
+fn difficulty_input(
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    controls: Res<Controls>,
+    mut difficulty: ResMut<DifficultySettings>,
+    mut difficulty_text_query: Query<(&DifficultyText, &mut Text)>,
+) {
+    if game_data.game_state != GameState::Menu {
+        return;
+    }
+
+    let count = DIFFICULTIES.len();
+    let index = DIFFICULTIES
+        .iter()
+        .position(|preset| *preset == difficulty.selected)
+        .unwrap_or(0);
+    if actions.just_pressed(Action::SteerLeft) {
+        difficulty.selected = DIFFICULTIES[(index + count - 1) % count];
+    }
+    if actions.just_pressed(Action::SteerRight) {
+        difficulty.selected = DIFFICULTIES[(index + 1) % count];
+    }
+
+    for (_difficulty_text, mut text) in &mut difficulty_text_query.iter() {
+        text.value = format!(
+            "Difficulty: < {} > ({} / {} to change)",
+            difficulty.selected.label(),
+            controls.describe(Action::SteerLeft),
+            controls.describe(Action::SteerRight)
+        );
+    }
+}
+
+fn open_controls_input(
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+) {
+    if game_data.game_state == GameState::Menu && actions.just_pressed(Action::OpenControls) {
+        requests.send(GameStateRequest::Controls);
+    }
+}
+
+fn update_high_score_table(
+    game_data: Res<GameData>,
+    high_scores: Res<HighScores>,
+    selected: Res<SelectedHighScore>,
+    row: &HighScoreRow,
+    mut text: Mut<Text>,
+) {
+    if game_data.game_state != GameState::Menu {
+        return;
+    }
+
+    text.style.color = option_color(row.0 == selected.0 && !high_scores.entries.is_empty());
+    text.value = match high_scores.entries.get(row.0) {
+        Some(entry) => format!(
+            "{}. {}  {}  {:.0} m  {}  {}{}",
+            row.0 + 1,
+            entry.name,
+            entry.score,
+            entry.distance,
+            entry.difficulty.map_or("-", |difficulty| difficulty.label()),
+            format_date(entry.date),
+            if entry.replay.is_some() { "  (replay)" } else { "" }
+        ),
+        None if row.0 == 0 => "No runs yet".into(),
+        None => "".into(),
+    };
+}
+
+// digits typed on the menu build up a fixed seed for the next runs, backspace removes them
+fn seed_entry_input(
+    game_data: Res<GameData>,
+    mut run_seed: ResMut<RunSeed>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut character_reader: Local<EventReader<ReceivedCharacter>>,
+    character_events: Res<Events<ReceivedCharacter>>,
+    mut seed_text_query: Query<(&SeedText, &mut Text)>,
+) {
+    let characters: Vec<char> = character_reader
+        .iter(&character_events)
+        .map(|event| event.char)
+        .collect();
+    if game_data.game_state != GameState::Menu {
+        return;
+    }
+
+    for c in characters {
+        if let Some(digit) = c.to_digit(10) {
+            let seed = run_seed.fixed.unwrap_or(0);
+            if let Some(seed) = seed.checked_mul(10).and_then(|seed| seed.checked_add(digit as u64)) {
+                run_seed.fixed = Some(seed);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        run_seed.fixed = match run_seed.fixed {
+            Some(seed) if seed >= 10 => Some(seed / 10),
+            _ => None,
+        };
+    }
+
+    for (_seed_text, mut text) in &mut seed_text_query.iter() {
+        text.value = match run_seed.fixed {
+            Some(seed) => format!("Seed: {} (backspace to clear)", seed),
+            None => "Seed: random (type digits to pick one)".into(),
+        };
+    }
+}
+
+// menu up/down picks a high score, watch replay plays it back with the seed and difficulty it was recorded with
+fn high_score_replay_input(
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    high_scores: Res<HighScores>,
+    mut selected: ResMut<SelectedHighScore>,
+    mut run_seed: ResMut<RunSeed>,
+    mut difficulty: ResMut<DifficultySettings>,
+    mut replays: ResMut<Replays>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+) {
+    if game_data.game_state != GameState::Menu {
+        return;
+    }
+
+    let count = high_scores.entries.len();
+    if count == 0 {
+        return;
+    }
+    if actions.just_pressed(Action::MenuUp) {
+        selected.0 = (selected.0 + count - 1) % count;
+    }
+    if actions.just_pressed(Action::MenuDown) {
+        selected.0 = (selected.0 + 1) % count;
+    }
+    selected.0 = selected.0.min(count - 1);
+
+    if !actions.just_pressed(Action::WatchReplay) {
+        return;
+    }
+    let file_name = match &high_scores.entries[selected.0].replay {
+        Some(file_name) => file_name,
+        None => {
+            println!("no replay was saved for this run");
+            return;
+        }
+    };
+    match Replay::load(file_name) {
+        Ok(replay) => {
+            replays.watch(replay, &mut run_seed, &mut difficulty);
+            requests.send(GameStateRequest::NewRun);
+        }
+        Err(e) => println!("couldn't load replay {}: {}", file_name, e),
+    }
+}
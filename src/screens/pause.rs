@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+
+use crate::{
+    controls::Actions,
+    gamedata::GameData,
+    gamestate::{GameState, GameStateRequest},
+};
+
+use super::{
+    assets::ScreenAssets,
+    components::option_list::{navigate, option_color, option_text, OptionItem, CONFIRM_DELAY},
+    PauseMenu,
+};
+
+const OPTIONS: &[&str] = &["Resume", "Quit to menu"];
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(setup.system())
+            .add_system(pause_visibility.system())
+            .add_system(pause_input.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>, screen_assets: Res<ScreenAssets>) {
+    let font = screen_assets.default_font;
+    commands.spawn(NodeComponents {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+        draw: Draw {
+            is_visible: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    }).with(PauseMenu).with_children(|parent| {
+        parent.spawn(TextComponents {
+            style: Style {
+                margin: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            text: Text {
+                value: "Paused".into(),
+                font,
+                style: TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            },
+            draw: Draw {
+                is_visible: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }).with(PauseMenu);
+
+        for (index, label) in OPTIONS.iter().enumerate() {
+            let mut option = option_text(font, label);
+            option.draw.is_visible = false;
+            parent.spawn(option).with(PauseMenu).with(OptionItem { index });
+        }
+    });
+}
+
+fn pause_visibility(game_data: Res<GameData>, _pause_menu: &PauseMenu, mut draw: Mut<Draw>) {
+    draw.is_visible = game_data.game_state == GameState::Paused;
+}
+
+fn pause_input(
+    time: Res<Time>,
+    game_data: Res<GameData>,
+    actions: Res<Actions>,
+    mut selected: Local<usize>,
+    mut shown_for: Local<f32>,
+    mut requests: ResMut<Events<GameStateRequest>>,
+    mut option_query: Query<(&PauseMenu, &OptionItem, &mut Text)>,
+) {
+    if game_data.game_state != GameState::Paused {
+        *selected = 0;
+        *shown_for = 0.0;
+        return;
+    }
+    *shown_for += time.delta_seconds;
+
+    if navigate(&actions, &mut selected, OPTIONS.len()) && *shown_for >= CONFIRM_DELAY {
+        match *selected {
+            0 => requests.send(GameStateRequest::Resume),
+            _ => requests.send(GameStateRequest::MainMenu),
+        }
+    }
+
+    for (_pause_menu, option, mut text) in &mut option_query.iter() {
+        text.style.color = option_color(option.index == *selected);
+    }
+}
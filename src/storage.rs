@@ -0,0 +1,48 @@
+use std::{env, fs, path::PathBuf};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+const APP_DIR: &str = "bevy-asteroid-game-showcase";
+
+// the per-user directory the game keeps its files in, following each platform's convention
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+pub fn load_ron<T: DeserializeOwned>(file_name: &str) -> Result<T, String> {
+    let path = data_dir().join(file_name);
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    ron::de::from_str(&contents).map_err(|e| format!("couldn't parse {}: {}", path.display(), e))
+}
+
+pub fn save_ron<T: Serialize>(file_name: &str, value: &T) -> Result<(), String> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    let path = dir.join(file_name);
+    let contents = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| format!("couldn't serialize {}: {}", path.display(), e))?;
+    fs::write(&path, contents).map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
+
+pub fn load_bytes(file_name: &str) -> Result<Vec<u8>, String> {
+    let path = data_dir().join(file_name);
+    fs::read(&path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+}
+
+pub fn save_bytes(file_name: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = data_dir().join(file_name);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("couldn't create {}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, bytes).map_err(|e| format!("couldn't write {}: {}", path.display(), e))
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::{
+    gamedata::GameData,
+    gamestate::{GameState, NewRunEvent},
+    replay::PlayerInput,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+};
+
+pub const BOOST_SPEED_SCALE: f32 = 1.5; // of the asteroids' speed, while boost is held
+const BRAKE_SPEED_SCALE: f32 = 0.6;
+const THROTTLE_RATE: f32 = 1.0; // how fast the speed scale moves towards the held one, per second
+const ENERGY_DRAIN: f32 = 0.3; // energy goes from 0 to 1, boosting or braking uses this per second
+const ENERGY_REGEN: f32 = 0.15; // regained per second while neither is held
+const RECHARGED: f32 = 0.25; // empty energy has to be back over this before it can be used again
+
+// boosting and braking for the current run. both scale the asteroids' speed and drain the same
+// energy, which only comes back while neither is held
+pub struct Throttle {
+    pub energy: f32,
+    pub exhausted: bool,
+    pub speed_scale: f32,       // what the asteroids' speed is scaled by
+    previous_speed_scale: f32, // speed_scale one simulation step ago, for interpolation
+}
+
+impl Default for Throttle {
+    fn default() -> Throttle {
+        Throttle {
+            energy: 1.0,
+            exhausted: false,
+            speed_scale: 1.0,
+            previous_speed_scale: 1.0,
+        }
+    }
+}
+
+impl Throttle {
+    // advances one simulation step. holding both cancels out
+    fn step(&mut self, input: &PlayerInput, delta_seconds: f32) {
+        self.previous_speed_scale = self.speed_scale;
+
+        let held_scale = match (input.boost, input.brake) {
+            (true, false) => BOOST_SPEED_SCALE,
+            (false, true) => BRAKE_SPEED_SCALE,
+            _ => 1.0,
+        };
+        let target_scale = if held_scale != 1.0 && !self.exhausted {
+            self.energy = (self.energy - ENERGY_DRAIN * delta_seconds).max(0.0);
+            self.exhausted = self.energy == 0.0;
+            held_scale
+        } else {
+            self.energy = (self.energy + ENERGY_REGEN * delta_seconds).min(1.0);
+            if self.exhausted && self.energy > RECHARGED {
+                self.exhausted = false;
+            }
+            1.0
+        };
+        let change = THROTTLE_RATE * delta_seconds;
+        self.speed_scale += (target_scale - self.speed_scale).max(-change).min(change);
+    }
+
+    // makes the last step the only state, for when nothing is simulated
+    fn settle(&mut self) {
+        self.previous_speed_scale = self.speed_scale;
+    }
+
+    pub fn interpolated_speed_scale(&self, alpha: f32) -> f32 {
+        lerp(self.previous_speed_scale, self.speed_scale, alpha)
+    }
+}
+
+pub struct ThrottlePlugin;
+
+impl Plugin for ThrottlePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Throttle::default())
+            .add_system_to_stage(stage::PRE_UPDATE, reset_throttle.system())
+            .add_simulation_system(update_throttle.system());
+    }
+}
+
+fn reset_throttle(
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    mut throttle: ResMut<Throttle>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    *throttle = Throttle::default();
+}
+
+// has to run before apply_difficulty, which scales the asteroids' speed by the result
+fn update_throttle(
+    timestep: Res<FixedTimestep>,
+    game_data: Res<GameData>,
+    player_input: Res<PlayerInput>,
+    mut throttle: ResMut<Throttle>,
+) {
+    if game_data.game_state != GameState::Playing {
+        throttle.settle();
+        return;
+    }
+
+    throttle.step(&player_input, timestep.step);
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+
+use crate::{gamedata::GameData, gamestate::GameState};
+
+// the gameplay systems added with add_simulation_system all run once per simulation step, in the
+// order they were added, FixedTimestep::steps times per frame and by FixedTimestep::step seconds
+// each. whatever one system does in a step is there for the next, so the game plays the same at
+// any frame rate. systems in stage::UPDATE then interpolate transforms between the last two steps
+// using alpha
+const SIMULATION: &str = "simulation";
+
+pub const STEP: f32 = 1.0 / 120.0;
+const MAX_STEPS: u32 = 12; // don't try to catch up on more than 0.1s after a hitch
+
+pub struct FixedTimestep {
+    pub step: f32,
+    pub steps: u32,  // how many steps to simulate this frame
+    pub alpha: f32,  // how far between the last step and the next one this frame is, 0..1
+    pub frame_time: Option<f32>, // when set, every frame counts as this long instead of the real delta
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f32) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            steps: 0,
+            alpha: 0.0,
+            frame_time: None,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta_seconds: f32) {
+        self.accumulator += delta_seconds;
+        self.steps = (self.accumulator / self.step) as u32;
+        self.accumulator -= self.steps as f32 * self.step;
+        if self.steps > MAX_STEPS {
+            self.steps = MAX_STEPS;
+        }
+        self.alpha = self.accumulator / self.step;
+    }
+}
+
+// what the simulation stage runs every step, see add_simulation_system
+#[derive(Default)]
+struct SimulationSystems {
+    systems: Vec<Box<dyn System>>,
+    initialized: bool,
+}
+
+pub trait AddSimulationSystem {
+    fn add_simulation_system(&mut self, system: Box<dyn System>) -> &mut Self;
+}
+
+impl AddSimulationSystem for AppBuilder {
+    fn add_simulation_system(&mut self, system: Box<dyn System>) -> &mut Self {
+        self.resources()
+            .get_mut::<SimulationSystems>()
+            .expect("TimestepPlugin has to be added before any simulation system")
+            .systems
+            .push(system);
+        self
+    }
+}
+
+pub fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+pub struct TimestepPlugin;
+
+impl Plugin for TimestepPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(FixedTimestep::new(STEP))
+            .add_resource(SimulationSystems::default())
+            .add_stage_after(stage::PRE_UPDATE, SIMULATION)
+            .add_system_to_stage(stage::PRE_UPDATE, accumulate_time.system())
+            .add_system_to_stage(SIMULATION, run_simulation.thread_local_system());
+    }
+}
+
+fn accumulate_time(time: Res<Time>, game_data: Res<GameData>, mut timestep: ResMut<FixedTimestep>) {
+    if game_data.game_state == GameState::Paused {
+        timestep.steps = 0;
+        return;
+    }
+    let delta_seconds = timestep.frame_time.unwrap_or(time.delta_seconds);
+    timestep.advance(delta_seconds);
+}
+
+// runs the simulation systems one after the other for every step, applying each one's commands
+// before the next runs, so what's spawned or despawned in a step is already there for the rest of it
+fn run_simulation(world: &mut World, resources: &mut Resources) {
+    let steps = resources.get::<FixedTimestep>().map_or(0, |timestep| timestep.steps);
+    let (mut systems, initialized) = match resources.get_mut::<SimulationSystems>() {
+        Some(mut simulation) => (std::mem::take(&mut simulation.systems), simulation.initialized),
+        None => return,
+    };
+    if !initialized {
+        for system in &mut systems {
+            system.initialize(resources);
+        }
+    }
+    for _ in 0..steps {
+        for system in &mut systems {
+            system.update_archetype_access(world);
+            system.run(world, resources);
+            system.run_thread_local(world, resources);
+        }
+    }
+    if let Some(mut simulation) = resources.get_mut::<SimulationSystems>() {
+        simulation.systems = systems;
+        simulation.initialized = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the steps each of `frames` frames of frame_time seconds simulates
+    fn steps_per_frame(frame_time: f32, frames: usize) -> Vec<u32> {
+        let mut timestep = FixedTimestep::new(STEP);
+        (0..frames)
+            .map(|_| {
+                timestep.advance(frame_time);
+                timestep.steps
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frame_rates_simulate_the_same_steps() {
+        let slow = steps_per_frame(STEP * 4.0, 30); // 30 fps
+        let fast = steps_per_frame(STEP * 0.5, 240); // 240 fps
+        assert!(slow.iter().all(|steps| *steps == 4));
+        assert_eq!(slow.iter().sum::<u32>(), fast.iter().sum::<u32>());
+    }
+
+    #[test]
+    fn catching_up_is_capped() {
+        let mut timestep = FixedTimestep::new(STEP);
+        timestep.advance(1.0);
+        assert_eq!(timestep.steps, MAX_STEPS);
+    }
+}
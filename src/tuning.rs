@@ -0,0 +1,189 @@
+use std::{fs, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asteroids::AsteroidSpawner,
+    lives::{LivesTuning, MAX_SHIELD_CHARGES},
+    rocket::RocketSpecs,
+};
+
+// checked into the repo next to the models, not per user like the files in storage::data_dir
+const FILE_PATH: &str = "assets/tuning.ron";
+const POLL_INTERVAL: f32 = 0.5; // seconds between checks for a changed file
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AsteroidTuning {
+    pub z_spawn_position: f32, // how far ahead of the rocket rows spawn, negative is ahead
+    pub z_interval: f32,       // the distance between rows
+    pub z_rand: f32,           // how far past its row an asteroid can be pushed back
+    pub min_x_spacing: f32,    // the closest two asteroids in a row can be on the x axis
+    pub z_velocity: f32,       // the speed the asteroids come at you
+    pub max_x: f32,            // where the border asteroids are
+}
+
+// every number a designer would want to change without recompiling. changing it changes
+// how runs play out, so replays only match when recorded and watched with the same tuning
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tuning {
+    pub asteroids: AsteroidTuning,
+    pub rocket: RocketSpecs,
+    pub lives: LivesTuning,
+}
+
+impl Default for Tuning {
+    fn default() -> Tuning {
+        Tuning {
+            asteroids: AsteroidTuning {
+                z_spawn_position: -300.0,
+                z_interval: 24.0,
+                z_rand: 15.0,
+                min_x_spacing: 2.0,
+                z_velocity: 60.0,
+                max_x: 12.0,
+            },
+            rocket: RocketSpecs {
+                max_x_velocity: 18.0,
+                steering_speed: 1.0,
+                max_steering_angle: 2.0 * 30.0 / 180.0,
+            },
+            lives: LivesTuning {
+                lives: 3,
+                shield_charges: 1,
+            },
+        }
+    }
+}
+
+impl Tuning {
+    pub fn load() -> Result<Tuning, String> {
+        let contents =
+            fs::read_to_string(FILE_PATH).map_err(|e| format!("couldn't read {}: {}", FILE_PATH, e))?;
+        let tuning: Tuning =
+            ron::de::from_str(&contents).map_err(|e| format!("couldn't parse {}: {}", FILE_PATH, e))?;
+        let problems = tuning.validate();
+        if problems.is_empty() {
+            Ok(tuning)
+        } else {
+            Err(format!("{} has invalid values:\n  {}", FILE_PATH, problems.join("\n  ")))
+        }
+    }
+
+    // everything that would make the game misbehave, one message per value
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut check = |name: &str, value: f32, valid: bool, expected: &str| {
+            if !value.is_finite() || !valid {
+                problems.push(format!("{} is {}, expected {}", name, value, expected));
+            }
+        };
+
+        let asteroids = &self.asteroids;
+        check(
+            "asteroids.z_spawn_position",
+            asteroids.z_spawn_position,
+            asteroids.z_spawn_position < 0.0,
+            "a negative number (ahead of the rocket)",
+        );
+        check("asteroids.z_interval", asteroids.z_interval, asteroids.z_interval > 0.0, "more than 0");
+        check(
+            "asteroids.z_rand",
+            asteroids.z_rand,
+            asteroids.z_rand >= 0.0 && asteroids.z_rand < asteroids.z_interval,
+            "at least 0 and less than z_interval",
+        );
+        check("asteroids.max_x", asteroids.max_x, asteroids.max_x > 0.0, "more than 0");
+        // otherwise spawn_asteroids can't find room for a second asteroid in a row
+        check(
+            "asteroids.min_x_spacing",
+            asteroids.min_x_spacing,
+            asteroids.min_x_spacing >= 0.0 && asteroids.min_x_spacing < asteroids.max_x,
+            "at least 0 and less than max_x",
+        );
+        check("asteroids.z_velocity", asteroids.z_velocity, asteroids.z_velocity > 0.0, "more than 0");
+
+        let rocket = &self.rocket;
+        check("rocket.max_x_velocity", rocket.max_x_velocity, rocket.max_x_velocity > 0.0, "more than 0");
+        check("rocket.steering_speed", rocket.steering_speed, rocket.steering_speed > 0.0, "more than 0");
+        check(
+            "rocket.max_steering_angle",
+            rocket.max_steering_angle,
+            rocket.max_steering_angle > 0.0 && rocket.max_steering_angle < std::f32::consts::FRAC_PI_2,
+            "between 0 and a quarter turn (1.57)",
+        );
+
+        // a run with no lives would be over before it started
+        let lives = &self.lives;
+        check("lives.lives", lives.lives as f32, lives.lives >= 1, "at least 1");
+        // any more would quietly be dropped when a run starts
+        check(
+            "lives.shield_charges",
+            lives.shield_charges as f32,
+            lives.shield_charges <= MAX_SHIELD_CHARGES,
+            &format!("at most {}", MAX_SHIELD_CHARGES),
+        );
+        problems
+    }
+}
+
+pub struct TuningPlugin;
+
+// tracks the tuning file so it's only read again when it changes
+#[derive(Default)]
+struct TuningWatcher {
+    loaded: bool,
+    modified: Option<SystemTime>,
+    poll_timer: f32,
+    last_error: Option<String>,
+}
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(TuningWatcher::default())
+            .add_system_to_stage(stage::PRE_UPDATE, reload_tuning.system());
+    }
+}
+
+// reads the tuning file on the first frame and again whenever it's saved. a file that doesn't
+// parse or validate leaves the current values in place, so a typo never breaks a running game
+fn reload_tuning(
+    time: Res<Time>,
+    mut watcher: ResMut<TuningWatcher>,
+    mut asteroid_spawner: ResMut<AsteroidSpawner>,
+    mut rocket_specs: ResMut<RocketSpecs>,
+    mut lives_tuning: ResMut<LivesTuning>,
+) {
+    watcher.poll_timer -= time.delta_seconds;
+    if watcher.loaded && watcher.poll_timer > 0.0 {
+        return;
+    }
+    watcher.poll_timer = POLL_INTERVAL;
+
+    let modified = fs::metadata(FILE_PATH).and_then(|metadata| metadata.modified()).ok();
+    if watcher.loaded && modified == watcher.modified {
+        return;
+    }
+    let first_load = !watcher.loaded;
+    watcher.loaded = true;
+    watcher.modified = modified;
+
+    match Tuning::load() {
+        Ok(tuning) => {
+            asteroid_spawner.apply_tuning(&tuning.asteroids);
+            *rocket_specs = tuning.rocket;
+            *lives_tuning = tuning.lives;
+            watcher.last_error = None;
+            if !first_load {
+                println!("reloaded {}", FILE_PATH);
+            }
+        }
+        Err(e) => {
+            // only say it once, not every poll
+            if watcher.last_error.as_ref() != Some(&e) {
+                println!("{}; keeping the {} values", e, if first_load { "built-in" } else { "previous" });
+            }
+            watcher.last_error = Some(e);
+        }
+    }
+}
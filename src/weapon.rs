@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use crate::{
+    asteroids::{slot_offset, AsteroidGroup, AsteroidSlot, AsteroidSpawner, Drift},
+    collision::Collider,
+    destruction::DestroyAsteroidEvent,
+    gamedata::GameData,
+    gamestate::{GameState, NewRunEvent},
+    replay::PlayerInput,
+    rocket::RocketKinematics,
+    timestep::{lerp, AddSimulationSystem, FixedTimestep},
+};
+
+const FIRE_INTERVAL: f32 = 0.15; // seconds between shots while fire is held
+const HEAT_PER_SHOT: f32 = 0.12; // heat goes from 0 to 1, where the laser overheats
+const COOLING_RATE: f32 = 0.3; // heat lost per second
+const COOLED_DOWN: f32 = 0.25; // an overheated laser fires again once heat is back under this
+const LASER_SPEED: f32 = 150.0;
+const LASER_RANGE: f32 = 320.0; // a little past where rows spawn
+const LASER_RADIUS: f32 = 0.15;
+const MUZZLE_Z: f32 = -1.5; // the nose of the rocket
+const LASER_HEIGHT: f32 = 1.0; // the height asteroids sit at within their group
+
+// the forward laser's state for the current run
+#[derive(Default)]
+pub struct Weapon {
+    pub heat: f32,
+    pub overheated: bool,
+    cooldown: f32, // seconds until the next shot
+}
+
+// a shot in flight. x is relative to the asteroid field like the asteroids' own, so a shot flies
+// straight down its lane however the rocket steers after firing
+struct Laser {
+    x: f32,
+    z: f32,          // the simulated z
+    previous_z: f32, // z one simulation step ago, for interpolation
+}
+
+struct LaserAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Weapon::default())
+            .add_startup_system(setup.system())
+            .add_system_to_stage(stage::PRE_UPDATE, reset_weapon.system())
+            .add_simulation_system(fire_lasers.system())
+            .add_simulation_system(move_lasers.system())
+            .add_simulation_system(laser_hits.system())
+            .add_system(interpolate_lasers.system());
+    }
+}
+
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<StandardMaterial>>) {
+    commands.insert_resource(LaserAssets {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: materials.add(StandardMaterial {
+            albedo: Color::rgb(1.0, 0.2, 0.1),
+            shaded: false,
+            ..Default::default()
+        }),
+    });
+}
+
+fn reset_weapon(
+    mut commands: Commands,
+    mut new_run_reader: Local<EventReader<NewRunEvent>>,
+    new_run_events: Res<Events<NewRunEvent>>,
+    mut weapon: ResMut<Weapon>,
+    mut laser_query: Query<(Entity, &Laser)>,
+) {
+    if new_run_reader.iter(&new_run_events).next().is_none() {
+        return;
+    }
+
+    *weapon = Weapon::default();
+    for (entity, _laser) in &mut laser_query.iter() {
+        commands.despawn(entity);
+    }
+}
+
+// cools the laser and fires whenever it's held, ready and not overheated
+fn fire_lasers(
+    mut commands: Commands,
+    game_data: Res<GameData>,
+    timestep: Res<FixedTimestep>,
+    player_input: Res<PlayerInput>,
+    kinematics: Res<RocketKinematics>,
+    laser_assets: Res<LaserAssets>,
+    mut weapon: ResMut<Weapon>,
+) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    weapon.cooldown = (weapon.cooldown - timestep.step).max(0.0);
+    weapon.heat = (weapon.heat - COOLING_RATE * timestep.step).max(0.0);
+    if weapon.overheated && weapon.heat < COOLED_DOWN {
+        weapon.overheated = false;
+    }
+    if !player_input.fire || weapon.cooldown > 0.0 || weapon.overheated {
+        return;
+    }
+
+    weapon.cooldown = FIRE_INTERVAL;
+    weapon.heat += HEAT_PER_SHOT;
+    if weapon.heat >= 1.0 {
+        weapon.heat = 1.0;
+        weapon.overheated = true;
+    }
+    commands
+        .spawn(PbrComponents {
+            mesh: laser_assets.mesh,
+            material: laser_assets.material,
+            transform: Transform::new(Mat4::from_scale_rotation_translation(
+                Vec3::new(LASER_RADIUS, LASER_RADIUS, 2.0),
+                Quat::identity(),
+                Vec3::new(0.0, LASER_HEIGHT, MUZZLE_Z),
+            )),
+            ..Default::default()
+        })
+        .with(Laser {
+            x: kinematics.lateral_position,
+            z: MUZZLE_Z,
+            previous_z: MUZZLE_Z,
+        });
+}
+
+fn move_lasers(timestep: Res<FixedTimestep>, mut laser_query: Query<&mut Laser>) {
+    for mut laser in &mut laser_query.iter() {
+        laser.previous_z = laser.z;
+        laser.z -= LASER_SPEED * timestep.step;
+    }
+}
+
+// sweeps every shot over the distance it closed on each row this step and stops it at the first
+// asteroid in the way. field asteroids break, the border just absorbs the shot
+fn laser_hits(
+    mut commands: Commands,
+    game_data: Res<GameData>,
+    timestep: Res<FixedTimestep>,
+    asteroid_spawner: Res<AsteroidSpawner>,
+    mut destroy_events: ResMut<Events<DestroyAsteroidEvent>>,
+    mut laser_query: Query<(Entity, &Laser)>,
+    mut asteroid_group_query: Query<(&AsteroidGroup, &Children)>,
+    slot_query: Query<(&AsteroidSlot, &Collider, &Transform)>,
+    drift_query: Query<&Drift>,
+) {
+    if game_data.game_state != GameState::Playing {
+        return;
+    }
+
+    let laser_distance = LASER_SPEED * timestep.step; // how far a shot moved this step
+    let group_distance = asteroid_spawner.step_distance(timestep.step); // and every group
+    for (entity, laser) in &mut laser_query.iter() {
+        let mut hit: Option<(Entity, f32)> = None; // the asteroid and its z
+        for (asteroid_group, children) in &mut asteroid_group_query.iter() {
+            if !asteroid_group.active {
+                continue;
+            }
+            // the shot's path relative to the group, which is along z at the shot's x
+            let start = (laser.z + laser_distance) - (asteroid_group.z - group_distance);
+            let end = laser.z - asteroid_group.z;
+            for child in children.iter() {
+                match slot_query.get::<AsteroidSlot>(*child) {
+                    Ok(slot) if slot.in_use => {}
+                    _ => continue,
+                }
+                let (offset, radius) = match (
+                    slot_query.get::<Transform>(*child),
+                    slot_query.get::<Collider>(*child),
+                ) {
+                    (Ok(transform), Ok(collider)) => (
+                        slot_offset(&transform, drift_query.get::<Drift>(*child).ok().as_deref()),
+                        collider.radius,
+                    ),
+                    _ => continue,
+                };
+                // circles on the field's plane, the shot is level with the asteroids
+                let closest_z = offset.z().max(end.min(start)).min(end.max(start));
+                let dx = laser.x - offset.x();
+                let dz = closest_z - offset.z();
+                let reach = radius + LASER_RADIUS;
+                if dx * dx + dz * dz >= reach * reach {
+                    continue;
+                }
+                // shots travel towards -z, so the first thing in the way has the largest z
+                let z = asteroid_group.z + offset.z();
+                if hit.map_or(true, |(_asteroid, hit_z)| z > hit_z) {
+                    hit = Some((*child, z));
+                }
+            }
+        }
+
+        match hit {
+            Some((asteroid, _z)) => {
+                commands.despawn(entity);
+                // border asteroids don't drift, and can't be destroyed
+                if drift_query.get::<Drift>(asteroid).is_ok() {
+                    destroy_events.send(DestroyAsteroidEvent { asteroid });
+                }
+            }
+            None if laser.z < -LASER_RANGE => commands.despawn(entity),
+            None => {}
+        }
+    }
+}
+
+fn interpolate_lasers(
+    timestep: Res<FixedTimestep>,
+    kinematics: Res<RocketKinematics>,
+    laser: &Laser,
+    mut transform: Mut<Transform>,
+) {
+    transform.set_translation(Vec3::new(
+        kinematics.interpolated_field_x(timestep.alpha) + laser.x,
+        LASER_HEIGHT,
+        lerp(laser.previous_z, laser.z, timestep.alpha),
+    ));
+}